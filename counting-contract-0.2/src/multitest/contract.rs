@@ -1,10 +1,10 @@
-use cosmwasm_std::{Addr, Coin, Empty, StdResult};
+use cosmwasm_std::{Addr, Coin, StdResult};
 use cw_multi_test::{App, ContractWrapper, Executor};
 
 use crate::{
     error::ContractError,
     execute, instantiate, migrate,
-    msg::{ExecMsg, InstantiateMsg, QueryMsg, ValueResp},
+    msg::{ExecMsg, InstantiateMsg, MigrateMsg, QueryMsg, ValueResp},
     query,
 };
 
@@ -22,7 +22,7 @@ impl CountingContract {
 
     #[track_caller]
     pub fn migrate(app: &mut App, contract: Addr, code_id: u64, sender: &Addr) -> StdResult<Self> {
-        app.migrate_contract(sender.clone(), contract.clone(), &Empty {}, code_id)
+        app.migrate_contract(sender.clone(), contract.clone(), &MigrateMsg {}, code_id)
             .map_err(|err| err.downcast().unwrap())
             .map(|_| Self(contract))
     }
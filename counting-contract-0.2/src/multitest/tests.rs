@@ -1,7 +1,8 @@
-use crate::msg::ValueResp;
-use cosmwasm_std::{coin, coins, Addr};
+use crate::msg::{api, ValueResp};
+use cosmwasm_std::{coin, coins, Addr, StdError};
 use counting_contract_0_1::multitest::contract::CountingContract as CountingContract_0_1;
-use cw_multi_test::App;
+use cw_multi_test::{App, Executor};
+use serde::Serialize;
 
 use crate::{
     error::ContractError,
@@ -395,6 +396,48 @@ fn migration() {
     );
 }
 
+#[test]
+fn migration_malformed_msg() {
+    let admin = Addr::unchecked("admin");
+    let owner = Addr::unchecked("owner");
+
+    #[derive(Serialize)]
+    struct MalformedMigrateMsg {
+        counter: u64,
+    }
+
+    let mut app = App::default();
+
+    let old_code_id = CountingContract_0_1::store_code(&mut app);
+    let new_code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract_0_1::instantiate(
+        &mut app,
+        old_code_id,
+        &owner,
+        "Counting contract",
+        &admin,
+        None,
+        coin(10, ATOM),
+    )
+    .unwrap();
+
+    let err: StdError = app
+        .migrate_contract(
+            admin,
+            contract.addr().clone(),
+            &MalformedMigrateMsg { counter: 10 },
+            new_code_id,
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert!(matches!(err, StdError::ParseErr { .. }));
+
+    let resp = contract.query_value(&app).unwrap();
+    assert_eq!(resp.value, 0);
+}
+
 #[test]
 fn migration_same_version() {
     let admin = Addr::unchecked("admin");
@@ -440,3 +483,14 @@ fn migration_same_version() {
         }
     );
 }
+
+#[test]
+fn schema() {
+    let api = api();
+
+    assert_eq!(api.contract_name, env!("CARGO_PKG_NAME"));
+    assert_eq!(api.contract_version, env!("CARGO_PKG_VERSION"));
+    assert!(api.migrate.is_some());
+
+    api.render().to_string().unwrap();
+}
@@ -1,24 +1,87 @@
-use cosmwasm_std::{Addr, Coin, DepsMut, MessageInfo, Response, StdResult};
-use cw2::{get_contract_version, set_contract_version};
+use cosmwasm_std::{
+    to_binary, Addr, BankMsg, Coin, Decimal, Deps, DepsMut, Env, MessageInfo, Order, Reply,
+    Response, StdError, StdResult, Storage, SubMsg, Uint128, WasmMsg,
+};
+use cw2::{get_contract_version, set_contract_version, ContractVersion};
 use cw_storage_plus::Item;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    error::ContractError,
-    msg::Parent,
-    state::{ParentDonation, State, PARENT_DONATION, STATE},
+    error::{ContractError, VersionError},
+    msg::{ExecMsg, InstantiateMsg, MigrateResp, MigrationKind, Parent},
+    state::{
+        ParentDonation, State, ALLOWED_DONORS, COUNTER_AT_HEIGHT, CW20_COUNTER,
+        CW20_MINIMAL_DONATION, DECAY_PER_BLOCK, EVENT_PREFIX, EXTRA_PARENTS, INIT_PARAMS,
+        LAST_MODIFIED_HEIGHT, LAST_TOUCH_HEIGHT, LOCK, MAX_COUNTER, MAX_FORWARD_DEPTH,
+        MILESTONE_SIZE, MINIMAL_DONATION_BPS, PARENT_DONATION, REFUND_EXCESS, RESERVE, ROUND_SIZE,
+        SCHEDULED_ESCROW, STATE, STIPEND, STREAK_WINDOW, TOLERANCE, WITHDRAW_COOLDOWN,
+        WITHDRAW_CUT,
+    },
 };
 
 const CONTRACT_NAME: &str = env!("CARGO_PKG_NAME");
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+pub const FORWARD_REPLY_ID: u64 = 1;
+pub const REGISTER_CHILD_REPLY_ID: u64 = 2;
+
 pub fn instantiate(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
-    counter: u64,
-    minimal_donation: Coin,
-    parent: Option<Parent>,
-) -> StdResult<Response> {
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    // Kept as sent, later config changes don't touch it
+    INIT_PARAMS.save(deps.storage, &msg)?;
+
+    let InstantiateMsg {
+        counter,
+        minimal_donation,
+        parent,
+        extra_parents,
+        allowed_donors,
+        event_prefix,
+        max_counter,
+        refund_excess,
+        round_size,
+        withdraw_cut,
+        withdraw_cooldown,
+        reserve,
+        stipend,
+        stipend_period,
+        decay_per_block,
+        max_forward_depth,
+        streak_window,
+        cw20_minimal_donation,
+        tolerance,
+        milestone_size,
+        minimal_donation_bps,
+    } = msg;
+
+    if let Some(max) = max_counter {
+        if counter > max {
+            return Err(ContractError::CounterTooHigh { max });
+        }
+    }
+
+    // A zero period would forward on the first donation and then keep underflowing its countdown
+    if parent
+        .iter()
+        .chain(&extra_parents)
+        .any(|parent| parent.donating_period == 0)
+    {
+        return Err(ContractError::InvalidPeriod {});
+    }
+
+    // Parents are paid from the same balance, so together they can't take more than all of it
+    let parts = parent
+        .iter()
+        .chain(&extra_parents)
+        .fold(Decimal::zero(), |sum, parent| sum + parent.part);
+    if parts > Decimal::one() {
+        return Err(ContractError::InvalidParentParts {});
+    }
+
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
     STATE.save(
@@ -30,6 +93,9 @@ pub fn instantiate(
             donating_parent: parent.as_ref().map(|p| p.donating_period),
         },
     )?;
+    COUNTER_AT_HEIGHT.save(deps.storage, env.block.height, &counter)?;
+    CW20_COUNTER.save(deps.storage, &0)?;
+    record_modified(deps.storage, &env)?;
 
     if let Some(parent) = parent {
         PARENT_DONATION.save(
@@ -38,16 +104,112 @@ pub fn instantiate(
                 address: deps.api.addr_validate(&parent.addr)?,
                 donating_parent_period: parent.donating_period,
                 part: parent.part,
+                fee: parent.fee,
             },
         )?;
     }
 
-    // Return a new `Response` with no data or log messages
-    Ok(Response::new())
+    for parent in extra_parents {
+        let address = deps.api.addr_validate(&parent.addr)?;
+        let parent_donation = ParentDonation {
+            address: address.clone(),
+            donating_parent_period: parent.donating_period,
+            part: parent.part,
+            fee: parent.fee,
+        };
+        EXTRA_PARENTS.save(
+            deps.storage,
+            &address,
+            &(parent_donation, parent.donating_period),
+        )?;
+    }
+
+    if let Some(allowed_donors) = allowed_donors {
+        let allowed_donors = allowed_donors
+            .iter()
+            .map(|addr| deps.api.addr_validate(addr))
+            .collect::<StdResult<Vec<_>>>()?;
+        ALLOWED_DONORS.save(deps.storage, &allowed_donors)?;
+    }
+
+    if let Some(event_prefix) = event_prefix {
+        EVENT_PREFIX.save(deps.storage, &event_prefix)?;
+    }
+
+    if let Some(max_counter) = max_counter {
+        MAX_COUNTER.save(deps.storage, &max_counter)?;
+    }
+
+    if refund_excess {
+        REFUND_EXCESS.save(deps.storage, &true)?;
+    }
+
+    if let Some(round_size) = round_size {
+        ROUND_SIZE.save(deps.storage, &round_size)?;
+    }
+
+    if let Some((beneficiary, fraction)) = withdraw_cut {
+        if fraction > Decimal::one() {
+            return Err(ContractError::InvalidWithdrawCut {});
+        }
+
+        let beneficiary = deps.api.addr_validate(&beneficiary)?;
+        WITHDRAW_CUT.save(deps.storage, &(beneficiary, fraction))?;
+    }
+
+    if let Some(withdraw_cooldown) = withdraw_cooldown {
+        WITHDRAW_COOLDOWN.save(deps.storage, &withdraw_cooldown)?;
+    }
+
+    if let Some(reserve) = reserve {
+        RESERVE.save(deps.storage, &reserve)?;
+    }
+
+    if let Some(stipend) = stipend {
+        STIPEND.save(deps.storage, &(stipend, stipend_period))?;
+    }
+
+    if let Some(decay_per_block) = decay_per_block {
+        DECAY_PER_BLOCK.save(deps.storage, &decay_per_block)?;
+        LAST_TOUCH_HEIGHT.save(deps.storage, &env.block.height)?;
+    }
+
+    if let Some(max_forward_depth) = max_forward_depth {
+        MAX_FORWARD_DEPTH.save(deps.storage, &max_forward_depth)?;
+    }
+
+    if let Some(streak_window) = streak_window {
+        STREAK_WINDOW.save(deps.storage, &streak_window)?;
+    }
+
+    if let Some((token, minimal_donation)) = cw20_minimal_donation {
+        let token = deps.api.addr_validate(&token)?;
+        CW20_MINIMAL_DONATION.save(deps.storage, &(token, minimal_donation))?;
+    }
+
+    if let Some(tolerance) = tolerance {
+        TOLERANCE.save(deps.storage, &tolerance)?;
+    }
+
+    if let Some(milestone_size) = milestone_size {
+        MILESTONE_SIZE.save(deps.storage, &milestone_size)?;
+    }
+
+    if minimal_donation_bps {
+        MINIMAL_DONATION_BPS.save(deps.storage, &true)?;
+    }
+
+    // Return a new `Response` with no data or log messages, only registering with the parent if there is one
+    Ok(Response::new().add_submessages(register_with_parent(deps.storage)?))
 }
 
-pub fn migrate(mut deps: DepsMut, parent: Option<Parent>) -> Result<Response, ContractError> {
-    let contract_version = get_contract_version(deps.storage)?;
+fn stored_version(storage: &dyn Storage) -> Result<ContractVersion, VersionError> {
+    Ok(get_contract_version(storage)?)
+}
+
+// Tells which migration steps a contract needs, from its cw2 record
+pub fn detect_migration(deps: Deps) -> Result<MigrationKind, ContractError> {
+    let contract_version = stored_version(deps.storage)?;
 
     if contract_version.contract != CONTRACT_NAME {
         return Err(ContractError::InvalidContract {
@@ -55,83 +217,355 @@ pub fn migrate(mut deps: DepsMut, parent: Option<Parent>) -> Result<Response, Co
         });
     }
 
-    let resp = match contract_version.version.as_str() {
-        "0.1.0" => migrate_0_1_0(deps.branch(), parent).map_err(ContractError::from)?,
-        "0.2.0" => migrate_0_2_0(deps.branch(), parent).map_err(ContractError::from)?,
-        version => {
-            if version == CONTRACT_VERSION {
-                return Ok(Response::new());
+    let kind = match contract_version.version.as_str() {
+        "0.1.0" => MigrationKind::FromV1,
+        "0.2.0" => MigrationKind::FromV2,
+        "0.3.0" => MigrationKind::FromV3,
+        CONTRACT_VERSION => MigrationKind::Current,
+        _ => MigrationKind::Unknown,
+    };
+
+    Ok(kind)
+}
+
+pub fn migrate(mut deps: DepsMut, parent: Option<Parent>) -> Result<Response, ContractError> {
+    let kind = detect_migration(deps.as_ref())?;
+    let from = get_contract_version(deps.storage)?.version;
+
+    if kind == MigrationKind::Unknown {
+        return Err(ContractError::InvalidContractVersion { version: from });
+    }
+
+    let mut resp = Response::new();
+
+    // Apply every remaining migration step in order, so any supported version reaches the latest schema.
+    // Each applied step is reported, so a chained migration shows every hop.
+    if kind == MigrationKind::FromV1 {
+        migrate_0_1_0(deps.branch())?;
+        resp = resp.add_attribute("migrated_step", "0.1.0->0.2.0");
+    }
+
+    if matches!(kind, MigrationKind::FromV1 | MigrationKind::FromV2) {
+        migrate_0_2_0(deps.branch(), parent)?;
+        resp = resp.add_attribute("migrated_step", "0.2.0->0.3.0");
+    }
+
+    if kind != MigrationKind::Current {
+        migrate_0_3_0(deps.branch())?;
+        resp = resp.add_attribute("migrated_step", format!("0.3.0->{CONTRACT_VERSION}"));
+    }
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    resp = resp.add_submessages(register_with_parent(deps.storage)?);
+
+    let data = to_binary(&MigrateResp {
+        from,
+        to: CONTRACT_VERSION.to_owned(),
+    })?;
+
+    Ok(resp.set_data(data))
+}
+
+pub fn reply(deps: DepsMut, reply: Reply) -> Result<Response, ContractError> {
+    match reply.id {
+        // The parent donation went through, so donations are unlocked again
+        FORWARD_REPLY_ID => {
+            LOCK.remove(deps.storage);
+            Ok(Response::new())
+        }
+        // Parents running older code don't know `RegisterChild`, the child works the same without it
+        REGISTER_CHILD_REPLY_ID => Ok(Response::new()),
+        id => Err(ContractError::UnknownReply { id }),
+    }
+}
+
+// Only the primary parent is told about this contract, as it is the one `Role` reports
+fn register_with_parent(storage: &dyn Storage) -> StdResult<Option<SubMsg>> {
+    let parent = match PARENT_DONATION.may_load(storage)? {
+        Some(parent) => parent,
+        None => return Ok(None),
+    };
+
+    let msg = WasmMsg::Execute {
+        contract_addr: parent.address.into_string(),
+        msg: to_binary(&ExecMsg::RegisterChild {})?,
+        funds: vec![],
+    };
+
+    Ok(Some(SubMsg::reply_on_error(msg, REGISTER_CHILD_REPLY_ID)))
+}
+
+// Bank sends with no coins are rejected, so zero amounts are dropped and nothing is sent when none is left
+pub fn send_if_nonempty(to: &Addr, coins: Vec<Coin>) -> Option<BankMsg> {
+    let coins: Vec<Coin> = coins
+        .into_iter()
+        .filter(|coin| !coin.amount.is_zero())
+        .collect();
+
+    if coins.is_empty() {
+        return None;
+    }
+
+    Some(BankMsg::Send {
+        to_address: to.to_string(),
+        amount: coins,
+    })
+}
+
+// The contract balance without the reserve, which withdraws never touch
+// Scheduled-donation deposits sit in the same bank balance, but aren't the contract's to spend
+pub fn held_balance(deps: Deps, env: &Env) -> StdResult<Vec<Coin>> {
+    let mut balance = deps.querier.query_all_balances(&env.contract.address)?;
+
+    for coin in &mut balance {
+        let escrowed = SCHEDULED_ESCROW
+            .may_load(deps.storage, &coin.denom)?
+            .unwrap_or_default();
+        coin.amount = coin.amount.saturating_sub(escrowed);
+    }
+
+    balance.retain(|coin| !coin.amount.is_zero());
+
+    Ok(balance)
+}
+
+// Same as `held_balance`, for a single denom
+pub fn held_amount(deps: Deps, env: &Env, denom: &str) -> StdResult<Uint128> {
+    let balance = deps.querier.query_balance(&env.contract.address, denom)?;
+    let escrowed = SCHEDULED_ESCROW
+        .may_load(deps.storage, denom)?
+        .unwrap_or_default();
+
+    Ok(balance.amount.saturating_sub(escrowed))
+}
+
+pub fn available_balance(deps: Deps, env: &Env) -> StdResult<Vec<Coin>> {
+    let mut balance = held_balance(deps, env)?;
+
+    if let Some(reserve) = RESERVE.may_load(deps.storage)? {
+        for coin in &mut balance {
+            if coin.denom == reserve.denom {
+                coin.amount = coin.amount.saturating_sub(reserve.amount);
+            }
+        }
+
+        balance.retain(|coin| !coin.amount.is_zero());
+    }
+
+    Ok(balance)
+}
+
+// The primary parent first, then the extra ones, each with the countdown to its next forwarding
+pub fn parents(storage: &dyn Storage) -> StdResult<Vec<(ParentDonation, u64)>> {
+    let mut parents = vec![];
+
+    if let Some(parent_donation) = PARENT_DONATION.may_load(storage)? {
+        let countdown = STATE
+            .load(storage)?
+            .donating_parent
+            .unwrap_or(parent_donation.donating_parent_period);
+        parents.push((parent_donation, countdown));
+    }
+
+    for entry in EXTRA_PARENTS.range(storage, None, None, Order::Ascending) {
+        let (_, parent) = entry?;
+        parents.push(parent);
+    }
+
+    Ok(parents)
+}
+
+// What a forward sends to the parent out of `balance`, after its fee is kept back
+pub fn parent_share(balance: &[Coin], parent_donation: &ParentDonation) -> Vec<Coin> {
+    let mut funds: Vec<Coin> = balance
+        .iter()
+        .map(|coin| Coin {
+            denom: coin.denom.clone(),
+            amount: coin.amount * parent_donation.part,
+        })
+        .collect();
+
+    // The fee stays in this contract
+    if let Some(fee) = &parent_donation.fee {
+        for coin in &mut funds {
+            if coin.denom == fee.denom {
+                coin.amount = coin.amount.saturating_sub(fee.amount);
             }
+        }
+
+        funds.retain(|coin| !coin.amount.is_zero());
+    }
 
-            return Err(ContractError::InvalidContractVersion {
-                version: version.into(),
+    funds
+}
+
+// Takes the beneficiary's part out of `balance`, leaving what the owner gets
+pub fn take_withdraw_cut(
+    storage: &dyn Storage,
+    balance: &mut Vec<Coin>,
+) -> StdResult<Option<(Addr, Vec<Coin>)>> {
+    let (beneficiary, fraction) = match WITHDRAW_CUT.may_load(storage)? {
+        Some(withdraw_cut) => withdraw_cut,
+        None => return Ok(None),
+    };
+
+    let mut cut = vec![];
+    for coin in balance.iter_mut() {
+        let amount = coin.amount * fraction;
+        coin.amount -= amount;
+
+        if !amount.is_zero() {
+            cut.push(Coin {
+                denom: coin.denom.clone(),
+                amount,
             });
         }
+    }
+
+    balance.retain(|coin| !coin.amount.is_zero());
+
+    if cut.is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Some((beneficiary, cut)))
+}
+
+// The stored counter only changes on writes, the decay since the last one is applied on read
+pub fn decayed_counter(storage: &dyn Storage, counter: u64, height: u64) -> StdResult<u64> {
+    let decay_per_block = match DECAY_PER_BLOCK.may_load(storage)? {
+        Some(decay_per_block) => decay_per_block,
+        None => return Ok(counter),
     };
 
-    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    let last_touch = LAST_TOUCH_HEIGHT.may_load(storage)?.unwrap_or(height);
 
-    Ok(resp)
+    Ok(apply_decay(
+        counter,
+        decay_per_block,
+        height.saturating_sub(last_touch),
+    ))
 }
 
-pub fn migrate_0_1_0(deps: DepsMut, parent: Option<Parent>) -> StdResult<Response> {
-    const COUNTER: Item<u64> = Item::new("counter");
-    const MINIMAL_DONATION: Item<Coin> = Item::new("minimal_donation");
-    const OWNER: Item<Addr> = Item::new("owner");
+// The native and cw20 counters together, as `Value` reports them and every goal is measured
+pub fn total_counter(storage: &dyn Storage, height: u64) -> StdResult<u64> {
+    let counter = STATE.load(storage)?.counter;
+    let counter = decayed_counter(storage, counter, height)?;
 
-    let counter = COUNTER.load(deps.storage)?;
-    let minimal_donation = MINIMAL_DONATION.load(deps.storage)?;
-    let owner = OWNER.load(deps.storage)?;
+    Ok(counter + CW20_COUNTER.may_load(storage)?.unwrap_or_default())
+}
 
-    STATE.save(
-        deps.storage,
-        &State {
-            counter,
-            minimal_donation,
-            owner,
-            donating_parent: parent.as_ref().map(|p| p.donating_period),
-        },
-    )?;
+// Snapshots go after `touch_counter`, so the freshly written counter isn't decayed again
+pub fn snapshot_counter(storage: &mut dyn Storage, height: u64) -> StdResult<()> {
+    let counter = total_counter(storage, height)?;
+    COUNTER_AT_HEIGHT.save(storage, height, &counter)
+}
 
-    if let Some(parent) = parent {
-        PARENT_DONATION.save(
-            deps.storage,
-            &ParentDonation {
-                address: deps.api.addr_validate(&parent.addr)?,
-                donating_parent_period: parent.donating_period,
-                part: parent.part,
-            },
-        )?;
+// What is left of `counter` after `blocks` blocks without a donation
+pub fn apply_decay(counter: u64, decay_per_block: u64, blocks: u64) -> u64 {
+    counter.saturating_sub(decay_per_block.saturating_mul(blocks))
+}
+
+// Marks the stored counter as current at `height`, whenever it is written with decay enabled
+pub fn touch_counter(storage: &mut dyn Storage, height: u64) -> StdResult<()> {
+    if DECAY_PER_BLOCK.may_load(storage)?.is_some() {
+        LAST_TOUCH_HEIGHT.save(storage, &height)?;
     }
 
-    Ok(Response::new())
+    Ok(())
+}
+
+// Any exec may change what a cache holds, and a failed one is reverted along with this record
+pub fn record_modified(storage: &mut dyn Storage, env: &Env) -> StdResult<()> {
+    LAST_MODIFIED_HEIGHT.save(storage, &env.block.height)
 }
 
-pub fn migrate_0_2_0(deps: DepsMut, parent: Option<Parent>) -> StdResult<Response> {
-    #[derive(Serialize, Deserialize)]
-    struct OldState {
-        pub counter: u64,
-        pub minimal_donation: Coin,
-        pub owner: Addr,
+pub fn event_prefix(deps: Deps) -> StdResult<Option<String>> {
+    EVENT_PREFIX.may_load(deps.storage)
+}
+
+// Prefix every attribute key, so they don't collide with attributes of other contracts in the same transaction.
+// Custom events like `receipt` are prefixed the same way.
+pub fn prefix_attributes(mut resp: Response, event_prefix: Option<String>) -> Response {
+    if let Some(prefix) = event_prefix {
+        let events = resp
+            .events
+            .iter_mut()
+            .flat_map(|event| &mut event.attributes);
+        for attr in resp.attributes.iter_mut().chain(events) {
+            attr.key = format!("{prefix}/{}", attr.key);
+        }
     }
 
-    const OLD_STATE: Item<OldState> = Item::new("state");
+    resp
+}
+
+// State layout of the 0.2.0 version, strict so the current `State` is never mistaken for it
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct StateV0_2 {
+    pub counter: u64,
+    pub minimal_donation: Coin,
+    pub owner: Addr,
+}
+
+const STATE_V0_2: Item<StateV0_2> = Item::new("state");
+
+// Reads the 0.1.0 layout, which kept every field in a separate item
+fn load_state_0_1_0(storage: &dyn Storage) -> StdResult<StateV0_2> {
+    const COUNTER: Item<u64> = Item::new("counter");
+    const MINIMAL_DONATION: Item<Coin> = Item::new("minimal_donation");
+    const OWNER: Item<Addr> = Item::new("owner");
+
+    Ok(StateV0_2 {
+        counter: COUNTER.load(storage)?,
+        minimal_donation: MINIMAL_DONATION.load(storage)?,
+        owner: OWNER.load(storage)?,
+    })
+}
 
-    let OldState {
+// Maps the 0.2.0 layout onto the current `State`
+fn map_state_0_2_0(old_state: StateV0_2, parent: Option<&Parent>) -> State {
+    let StateV0_2 {
         counter,
         minimal_donation,
         owner,
-    } = OLD_STATE.load(deps.storage)?;
+    } = old_state;
 
-    STATE.save(
-        deps.storage,
-        &State {
-            counter,
-            minimal_donation,
-            owner,
-            donating_parent: parent.as_ref().map(|p| p.donating_period),
-        },
-    )?;
+    State {
+        counter,
+        minimal_donation,
+        owner,
+        donating_parent: parent.map(|p| p.donating_period),
+    }
+}
+
+// The version record may be stale, so the storage itself is checked before each step
+pub fn migrate_0_1_0(deps: DepsMut) -> Result<(), ContractError> {
+    // The 0.1.0 layout has no "state" item at all
+    if STATE.may_load(deps.storage)?.is_some() {
+        return Err(ContractError::AlreadyMigrated {});
+    }
+
+    let state = load_state_0_1_0(deps.storage)?;
+    STATE_V0_2.save(deps.storage, &state)?;
+
+    Ok(())
+}
+
+pub fn migrate_0_2_0(deps: DepsMut, parent: Option<Parent>) -> Result<(), ContractError> {
+    let old_state = match STATE_V0_2.load(deps.storage) {
+        Ok(state) => state,
+        Err(_) if STATE.may_load(deps.storage)?.is_some() => {
+            return Err(ContractError::AlreadyMigrated {})
+        }
+        Err(err) => return Err(err.into()),
+    };
+
+    let state = map_state_0_2_0(old_state, parent.as_ref());
+
+    STATE.save(deps.storage, &state)?;
 
     if let Some(parent) = parent {
         PARENT_DONATION.save(
@@ -140,122 +574,2476 @@ pub fn migrate_0_2_0(deps: DepsMut, parent: Option<Parent>) -> StdResult<Respons
                 address: deps.api.addr_validate(&parent.addr)?,
                 donating_parent_period: parent.donating_period,
                 part: parent.part,
+                fee: parent.fee,
             },
         )?;
     }
 
-    Ok(Response::new())
+    Ok(())
 }
 
-// Define a new module called `query`
-pub mod query {
-    use cosmwasm_std::{Deps, StdResult};
-
-    // Import the `ValueResp` struct from the `msg` module
-    use crate::{msg::ValueResp, state::STATE};
+// 0.3.0 counted cw20 donations in the global counter, so all of it stays with the native ones
+pub fn migrate_0_3_0(deps: DepsMut) -> Result<(), ContractError> {
+    if CW20_COUNTER.may_load(deps.storage)?.is_some() {
+        return Err(ContractError::AlreadyMigrated {});
+    }
 
-    // Define a public function called `value` that takes no arguments and returns a `ValueResp` struct
-    pub fn value(deps: Deps) -> StdResult<ValueResp> {
-        let value: u64 = STATE.load(deps.storage)?.counter;
+    CW20_COUNTER.save(deps.storage, &0)?;
 
-        Ok(ValueResp { value })
-    }
+    Ok(())
 }
 
-// Define a new module called `exec`
-pub mod exec {
-    use cosmwasm_std::{
-        to_binary, BankMsg, Coin, DepsMut, Env, MessageInfo, Response, StdResult, Uint128, WasmMsg,
-    };
+// Reconstructs the `State` which `migrate` would produce, without writing anything
+pub fn preview_migrated_state(deps: Deps, parent: Option<&Parent>) -> StdResult<State> {
+    let version = get_contract_version(deps.storage)?.version;
 
-    use crate::{
-        error::ContractError,
-        msg::ExecMsg,
-        state::{PARENT_DONATION, STATE},
+    let state = match version.as_str() {
+        CONTRACT_VERSION | "0.3.0" => STATE.load(deps.storage)?,
+        "0.1.0" => map_state_0_2_0(load_state_0_1_0(deps.storage)?, parent),
+        "0.2.0" => map_state_0_2_0(STATE_V0_2.load(deps.storage)?, parent),
+        version => {
+            return Err(StdError::generic_err(format!(
+                "Unsupported contract version for migration: {version}"
+            )))
+        }
     };
 
-    pub fn donate(deps: DepsMut, env: Env, info: MessageInfo) -> StdResult<Response> {
-        let mut state = STATE.load(deps.storage)?;
-        let mut resp = Response::new();
+    Ok(state)
+}
 
-        if state.minimal_donation.amount.is_zero()
-            || info.funds.iter().any(|coin| {
-                coin.denom == state.minimal_donation.denom
-                    && coin.amount >= state.minimal_donation.amount
-            })
-        {
-            state.counter += 1;
+// Define a new module called `query`
+pub mod query {
+    use cosmwasm_std::{to_binary, Addr, Coin, Decimal, Deps, Env, Order, StdError, StdResult};
+    use cw2::get_contract_version;
+    use cw_storage_plus::Bound;
+    use semver::Version;
 
-            if let Some(parent) = &mut state.donating_parent {
-                *parent -= 1;
+    // Import the `ValueResp` struct from the `msg` module
+    use crate::{
+        msg::{
+            AccountingResp, Cw20PolicyResp, DonationRecord, DonorCount, DonorTotal,
+            EffectivePolicyResp, FlagsResp, GoalProgressResp, InstantiateMsg, IntegrityCheckResp,
+            MigratedStateResp, MigrationKind, NextForwardResp, Parent, QueryMsg, RoleResp,
+            SchemaVersionResp, TopDonorsByValueResp, TopDonorsResp, ValueResp,
+        },
+        state::{
+            ACCEPT_FUNDS_WHEN_FREE, ALLOWED_DONORS, BLOCKED_DONORS, CHILDREN, COUNTER_AT_HEIGHT,
+            CW20_MINIMAL_DONATION, DECAY_PER_BLOCK, DONATIONS_RECORDED, DONOR_COUNTS, DONOR_TOTALS,
+            EVENT_PREFIX, GRANDFATHERED_UNTIL, INIT_PARAMS, LAST_DONOR, LAST_MODIFIED_HEIGHT,
+            LAST_WITHDRAW, MAX_COUNTER, MAX_DONATION, MAX_FORWARD_DEPTH, MILESTONE_SIZE,
+            MINIMAL_DONATION_BPS, PARENT_DONATION, PAUSED, PREDECESSOR, RECEIPTS_ISSUED,
+            RECENT_DONATIONS, RECENT_DONATIONS_SIZE, REFUNDABLE, REFUND_EXCESS, RESERVE,
+            ROUNDS_COMPLETED, ROUND_SIZE, SCHEDULED_DONATIONS, STATE, STIPEND, STREAKS,
+            STREAK_WINDOW, TOLERANCE, TOTAL_DONATED, TOTAL_WITHDRAWN, VANITY_COUNTER,
+            WITHDRAW_COOLDOWN, WITHDRAW_CUT, WITHDRAW_DESTINATION,
+        },
+    };
 
-                if *parent == 0 {
-                    let parent_donation = PARENT_DONATION.load(deps.storage)?;
+    use super::{
+        available_balance, detect_migration, held_balance, parent_share, parents,
+        take_withdraw_cut, total_counter, CONTRACT_VERSION,
+    };
 
-                    let funds: Vec<Coin> = deps
-                        .querier
-                        .query_all_balances(env.contract.address)?
-                        .into_iter()
-                        .map(|mut coin| {
-                            coin.amount = coin.amount * parent_donation.part;
-                            coin
-                        })
-                        .collect();
+    const DEFAULT_LIMIT: u32 = 10;
+    const MAX_LIMIT: u32 = 30;
 
-                    let msg = WasmMsg::Execute {
-                        contract_addr: parent_donation.address.to_string(),
-                        msg: to_binary(&ExecMsg::Donate {})?,
-                        funds,
-                    };
+    // Define a public function called `value` that returns the counter, decayed up to the current block, as a `ValueResp` struct
+    // The native and cw20 counters together, as before they were split
+    pub fn value(deps: Deps, env: Env) -> StdResult<ValueResp> {
+        let value = total_counter(deps.storage, env.block.height)?;
 
-                    resp = resp
-                        .add_message(msg)
-                        .add_attribute("donated_to_parent", parent_donation.address.to_string());
-                }
-            }
+        Ok(ValueResp { value })
+    }
 
-            STATE.save(deps.storage, &state)?;
+    // Only the decay is projected, donations made until then are not known yet
+    pub fn projected_at_height(deps: Deps, env: Env, height: u64) -> StdResult<ValueResp> {
+        if height < env.block.height {
+            return Err(StdError::generic_err(
+                "Invalid height - it can't be in the past, use CounterAtHeight for that",
+            ));
         }
 
-        resp = resp
-            .add_attribute("action", "donate")
-            .add_attribute("sender", info.sender.as_str())
-            .add_attribute("counter", state.counter.to_string());
+        let value = total_counter(deps.storage, height)?;
 
-        Ok(resp)
+        Ok(ValueResp { value })
     }
 
-    pub fn withdraw(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
-        let owner = STATE.load(deps.storage)?.owner;
-        if info.sender != owner {
-            return Err(ContractError::Unauthorized {
-                owner: owner.to_string(),
-            });
-        }
+    pub fn receipt_count(deps: Deps) -> StdResult<u64> {
+        Ok(RECEIPTS_ISSUED.may_load(deps.storage)?.unwrap_or_default())
+    }
 
-        let balance = deps.querier.query_all_balances(&env.contract.address)?;
+    pub fn vanity(deps: Deps) -> StdResult<ValueResp> {
+        let value = VANITY_COUNTER.may_load(deps.storage)?.unwrap_or_default();
 
-        // here msg.sender is this contract
-        let bank_msg = BankMsg::Send {
-            to_address: owner.to_string(),
-            amount: balance,
+        Ok(ValueResp { value })
+    }
+
+    // Takes the latest snapshot recorded at or before the requested height
+    pub fn counter_at_height(deps: Deps, height: u64) -> StdResult<ValueResp> {
+        let (_, value) = COUNTER_AT_HEIGHT
+            .range(
+                deps.storage,
+                None,
+                Some(Bound::inclusive(height)),
+                Order::Descending,
+            )
+            .next()
+            .ok_or_else(|| StdError::not_found("counter at height"))??;
+
+        Ok(ValueResp { value })
+    }
+
+    pub fn children(
+        deps: Deps,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<Vec<Addr>> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let start_after = start_after
+            .map(|addr| deps.api.addr_validate(&addr))
+            .transpose()?;
+        let start = start_after.as_ref().map(Bound::exclusive);
+
+        CHILDREN
+            .keys(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .collect()
+    }
+
+    pub fn total_donated_paged(
+        deps: Deps,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<Vec<Coin>> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let start = start_after.as_deref().map(Bound::exclusive);
+
+        TOTAL_DONATED
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .map(|item| item.map(|(denom, amount)| Coin { denom, amount }))
+            .collect()
+    }
+
+    pub fn total_withdrawn(deps: Deps) -> StdResult<Vec<Coin>> {
+        TOTAL_WITHDRAWN
+            .range(deps.storage, None, None, Order::Ascending)
+            .map(|item| item.map(|(denom, amount)| Coin { denom, amount }))
+            .collect()
+    }
+
+    // `donated - withdrawn == held` per denom, unless funds were sent outside of the contract's
+    // own messages
+    pub fn accounting(deps: Deps, env: Env) -> StdResult<AccountingResp> {
+        let donated = TOTAL_DONATED
+            .range(deps.storage, None, None, Order::Ascending)
+            .map(|item| item.map(|(denom, amount)| Coin { denom, amount }))
+            .collect::<StdResult<_>>()?;
+        let withdrawn = total_withdrawn(deps)?;
+        let held = held_balance(deps, &env)?;
+
+        Ok(AccountingResp {
+            donated,
+            withdrawn,
+            held,
+        })
+    }
+
+    // Maps iterate by key, not by value, so all the counts are loaded and sorted in memory.
+    // Only the returned list is bounded by the limit.
+    pub fn top_donors(deps: Deps, limit: Option<u32>) -> StdResult<TopDonorsResp> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+        let mut donors = DONOR_COUNTS
+            .range(deps.storage, None, None, Order::Ascending)
+            .map(|item| item.map(|(addr, count)| DonorCount { addr, count }))
+            .collect::<StdResult<Vec<_>>>()?;
+
+        // Ties are broken by address, so clients always get the same order
+        donors.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.addr.cmp(&b.addr)));
+        donors.truncate(limit);
+
+        Ok(TopDonorsResp { donors })
+    }
+
+    // Same as `top_donors`, ranked by the donated value instead
+    pub fn top_donors_by_value(deps: Deps, limit: Option<u32>) -> StdResult<TopDonorsByValueResp> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+        let mut donors = DONOR_TOTALS
+            .range(deps.storage, None, None, Order::Ascending)
+            .map(|item| item.map(|(addr, total)| DonorTotal { addr, total }))
+            .collect::<StdResult<Vec<_>>>()?;
+
+        donors.sort_by(|a, b| b.total.cmp(&a.total).then_with(|| a.addr.cmp(&b.addr)));
+        donors.truncate(limit);
+
+        Ok(TopDonorsByValueResp { donors })
+    }
+
+    pub fn needs_migration(deps: Deps) -> StdResult<bool> {
+        let version = get_contract_version(deps.storage)?.version;
+
+        Ok(version != CONTRACT_VERSION)
+    }
+
+    pub fn migration_kind(deps: Deps) -> StdResult<MigrationKind> {
+        detect_migration(deps).map_err(|err| StdError::generic_err(err.to_string()))
+    }
+
+    // Failures are reported instead of returned, a missing cw2 record shows as an empty version
+    pub fn integrity_check(deps: Deps) -> StdResult<IntegrityCheckResp> {
+        let cw2_version = get_contract_version(deps.storage)
+            .map(|version| version.version)
+            .unwrap_or_default();
+
+        Ok(IntegrityCheckResp {
+            cw2_version,
+            state_loadable: STATE.load(deps.storage).is_ok(),
+        })
+    }
+
+    pub fn schema_version(deps: Deps) -> StdResult<SchemaVersionResp> {
+        let version = get_contract_version(deps.storage)?.version;
+        let version = Version::parse(&version).map_err(|err| {
+            StdError::generic_err(format!("Invalid contract version {version}: {err}"))
+        })?;
+
+        Ok(SchemaVersionResp {
+            major: version.major,
+            minor: version.minor,
+            patch: version.patch,
+        })
+    }
+
+    // Fails when no campaign goal is set
+    pub fn goal_progress(deps: Deps, env: Env) -> StdResult<GoalProgressResp> {
+        let current = total_counter(deps.storage, env.block.height)?;
+        let goal = MAX_COUNTER.load(deps.storage)?;
+
+        let fraction = if current >= goal {
+            Decimal::one()
+        } else {
+            Decimal::from_ratio(current, goal)
+        };
+
+        Ok(GoalProgressResp {
+            current,
+            goal,
+            fraction,
+        })
+    }
+
+    // Without an allowed donors list everyone is allowed to donate
+    pub fn is_allowed_donor(deps: Deps, addr: String) -> StdResult<bool> {
+        let addr = deps.api.addr_validate(&addr)?;
+
+        let allowed = match ALLOWED_DONORS.may_load(deps.storage)? {
+            Some(allowed_donors) => allowed_donors.contains(&addr),
+            None => true,
+        };
+
+        Ok(allowed)
+    }
+
+    pub fn rounds_completed(deps: Deps) -> StdResult<u64> {
+        Ok(ROUNDS_COMPLETED.may_load(deps.storage)?.unwrap_or_default())
+    }
+
+    // Grandfathered denoms are accepted for the current minimal amount until their grace period ends
+    pub fn effective_policy(deps: Deps, env: Env) -> StdResult<EffectivePolicyResp> {
+        let minimal_donation = STATE.load(deps.storage)?.minimal_donation;
+
+        let grandfathered = GRANDFATHERED_UNTIL
+            .range(deps.storage, None, None, Order::Ascending)
+            .filter(|item| {
+                item.as_ref()
+                    .map_or(true, |(_, until)| env.block.height <= *until)
+            })
+            .map(|item| {
+                item.map(|(denom, _)| Coin {
+                    denom,
+                    amount: minimal_donation.amount,
+                })
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+
+        let mut accepted = vec![minimal_donation];
+        accepted.extend(grandfathered);
+
+        Ok(EffectivePolicyResp { accepted })
+    }
+
+    pub fn flags(deps: Deps) -> StdResult<FlagsResp> {
+        Ok(FlagsResp {
+            paused: PAUSED.may_load(deps.storage)?.unwrap_or_default(),
+            refund_excess: REFUND_EXCESS.may_load(deps.storage)?.unwrap_or_default(),
+            accept_funds_when_free: ACCEPT_FUNDS_WHEN_FREE
+                .may_load(deps.storage)?
+                .unwrap_or(true),
+            allowed_donors_only: ALLOWED_DONORS.may_load(deps.storage)?.is_some(),
+            has_parent: !parents(deps.storage)?.is_empty(),
+            has_goal: MAX_COUNTER.may_load(deps.storage)?.is_some(),
+            has_rounds: ROUND_SIZE.may_load(deps.storage)?.is_some(),
+            has_withdraw_cut: WITHDRAW_CUT.may_load(deps.storage)?.is_some(),
+            has_decay: DECAY_PER_BLOCK.may_load(deps.storage)?.is_some(),
+            minimal_donation_bps: MINIMAL_DONATION_BPS
+                .may_load(deps.storage)?
+                .unwrap_or_default(),
+            has_tolerance: TOLERANCE.may_load(deps.storage)?.is_some(),
+            has_reserve: RESERVE.may_load(deps.storage)?.is_some(),
+            has_withdraw_cooldown: WITHDRAW_COOLDOWN.may_load(deps.storage)?.is_some(),
+            has_stipend: STIPEND.may_load(deps.storage)?.is_some(),
+            refundable: REFUNDABLE.may_load(deps.storage)?.unwrap_or_default(),
+            has_milestones: MILESTONE_SIZE.may_load(deps.storage)?.is_some(),
+            has_streaks: STREAK_WINDOW.may_load(deps.storage)?.is_some(),
+            has_cw20_policy: CW20_MINIMAL_DONATION.may_load(deps.storage)?.is_some(),
+            has_max_forward_depth: MAX_FORWARD_DEPTH.may_load(deps.storage)?.is_some(),
+            has_event_prefix: EVENT_PREFIX.may_load(deps.storage)?.is_some(),
+        })
+    }
+
+    // Map entries are stored under a length-prefixed namespace, shown as `namespace/key`
+    #[cfg(feature = "debug")]
+    pub fn debug(deps: Deps) -> StdResult<crate::msg::DebugResp> {
+        use crate::msg::{DebugResp, StorageEntry};
+
+        fn format_key(key: &[u8]) -> String {
+            if let [high, low, rest @ ..] = key {
+                let len = u16::from_be_bytes([*high, *low]) as usize;
+                if len <= rest.len() {
+                    if let Ok(namespace) = std::str::from_utf8(&rest[..len]) {
+                        let key = String::from_utf8_lossy(&rest[len..]);
+                        return format!("{namespace}/{key}");
+                    }
+                }
+            }
+
+            String::from_utf8_lossy(key).into_owned()
+        }
+
+        let entries = deps
+            .storage
+            .range(None, None, Order::Ascending)
+            .map(|(key, value)| StorageEntry {
+                key: format_key(&key),
+                value: String::from_utf8_lossy(&value).into_owned(),
+            })
+            .collect();
+
+        Ok(DebugResp { entries })
+    }
+
+    // Runs the same reductions as `exec::withdraw`, nothing is withdrawable during the cooldown
+    pub fn withdrawable(deps: Deps, env: Env) -> StdResult<Vec<Coin>> {
+        if let Some(cooldown) = WITHDRAW_COOLDOWN.may_load(deps.storage)? {
+            if let Some(last) = LAST_WITHDRAW.may_load(deps.storage)? {
+                if env.block.time < last.plus_seconds(cooldown) {
+                    return Ok(vec![]);
+                }
+            }
+        }
+
+        let mut balance = available_balance(deps, &env)?;
+        take_withdraw_cut(deps.storage, &mut balance)?;
+
+        Ok(balance)
+    }
+
+    // Names are the snake_case message names. Some messages reuse another one's `action` attribute,
+    // e.g. `withdraw_to` and `withdraw_as` emit `withdraw`, and `donate_matching` emits `donate`.
+    pub fn allowed_actions(deps: Deps, env: Env, addr: String) -> StdResult<Vec<String>> {
+        let addr = deps.api.addr_validate(&addr)?;
+        let state = STATE.load(deps.storage)?;
+
+        let mut actions = vec![];
+
+        let allowed = match ALLOWED_DONORS.may_load(deps.storage)? {
+            Some(allowed_donors) => allowed_donors.contains(&addr),
+            None => true,
+        };
+
+        let can_donate = allowed
+            && !PAUSED.may_load(deps.storage)?.unwrap_or_default()
+            && !BLOCKED_DONORS.has(deps.storage, &addr);
+        if can_donate {
+            actions.extend(["donate", "donate_matching", "schedule_donation"]);
+        }
+
+        actions.push("fund");
+
+        if SCHEDULED_DONATIONS.has(deps.storage, &addr) {
+            actions.push("cancel_scheduled_donation");
+        }
+
+        // Anyone may apply a scheduled donation once it is due
+        let due = SCHEDULED_DONATIONS
+            .range(deps.storage, None, None, Order::Ascending)
+            .map(|entry| entry.map(|(_, scheduled)| scheduled.next_height))
+            .collect::<StdResult<Vec<_>>>()?
+            .into_iter()
+            .any(|next_height| next_height <= env.block.height);
+        if due {
+            actions.push("execute_scheduled");
+        }
+
+        if REFUNDABLE.may_load(deps.storage)?.unwrap_or_default()
+            && DONOR_TOTALS.has(deps.storage, &addr)
+        {
+            actions.push("request_refund");
+        }
+
+        if let Some((token, _)) = CW20_MINIMAL_DONATION.may_load(deps.storage)? {
+            if token == addr {
+                actions.push("receive");
+            }
+        }
+
+        // Only contracts reporting this one as their parent can register, and only once
+        let role: StdResult<RoleResp> = deps.querier.query_wasm_smart(&addr, &QueryMsg::Role {});
+        if let Ok(RoleResp::Child { parent }) = role {
+            if parent == env.contract.address && !CHILDREN.has(deps.storage, &addr) {
+                actions.push("register_child");
+            }
+        }
+
+        // Anyone may finalize once the goal is reached
+        if let Some(max_counter) = MAX_COUNTER.may_load(deps.storage)? {
+            if total_counter(deps.storage, env.block.height)? >= max_counter {
+                actions.push("finalize_campaign");
+            }
+        }
+
+        if PREDECESSOR.may_load(deps.storage)?.as_ref() == Some(&addr) {
+            actions.push("import_counter");
+        }
+
+        if addr == state.owner {
+            actions.extend([
+                "reset",
+                "withdraw",
+                "withdraw_to",
+                "add_allowed_donor",
+                "remove_allowed_donor",
+                "block_donor",
+                "unblock_donor",
+                "set_minimal_donation",
+                "set_accept_funds_when_free",
+                "update_config",
+                "increment_vanity",
+                "recompute_stats",
+                "evacuate",
+                "handover",
+                "set_predecessor",
+                "set_withdraw_destination",
+                "set_refundable",
+                "reconcile",
+                "sweep_cw20",
+                "withdraw_as",
+                "withdraw_to_many",
+            ]);
+
+            if PARENT_DONATION.may_load(deps.storage)?.is_some() {
+                actions.extend(["set_parent_period", "reset_parent_countdown"]);
+            }
+
+            if LAST_DONOR.may_load(deps.storage)?.is_some() {
+                actions.push("tip_last_donor");
+            }
+
+            if !parents(deps.storage)?.is_empty() {
+                actions.push("remove_parent");
+            }
+
+            if STIPEND.may_load(deps.storage)?.is_some() {
+                actions.push("claim_stipend");
+            }
+        }
+
+        Ok(actions.into_iter().map(str::to_owned).collect())
+    }
+
+    pub fn state_size(deps: Deps) -> StdResult<u64> {
+        let state = to_binary(&STATE.load(deps.storage)?)?;
+
+        Ok(state.len() as u64)
+    }
+
+    pub fn describe(deps: Deps) -> StdResult<String> {
+        let state = STATE.load(deps.storage)?;
+
+        let parents: Vec<String> = parents(deps.storage)?
+            .into_iter()
+            .map(|(parent, donations)| format!("{} in {} donations", parent.address, donations))
+            .collect();
+        let parent = match parents.is_empty() {
+            true => "none".to_string(),
+            false => parents.join(", "),
+        };
+
+        Ok(format!(
+            "owner: {}, minimal donation: {}, parent: {}, counter: {}",
+            state.owner, state.minimal_donation, parent, state.counter
+        ))
+    }
+
+    pub fn has_parent(deps: Deps) -> StdResult<bool> {
+        Ok(!parents(deps.storage)?.is_empty())
+    }
+
+    // With no primary parent, the first extra one is reported
+    pub fn role(deps: Deps) -> StdResult<RoleResp> {
+        let role = match parents(deps.storage)?.into_iter().next() {
+            Some((parent, _)) => RoleResp::Child {
+                parent: parent.address,
+            },
+            None => RoleResp::Root,
+        };
+
+        Ok(role)
+    }
+
+    pub fn parent_period(deps: Deps) -> StdResult<Option<u64>> {
+        let parent_donation = PARENT_DONATION.may_load(deps.storage)?;
+
+        Ok(parent_donation.map(|parent_donation| parent_donation.donating_parent_period))
+    }
+
+    pub fn would_forward_to_parent(deps: Deps) -> StdResult<bool> {
+        let parents = parents(deps.storage)?;
+
+        Ok(parents.iter().any(|(_, countdown)| *countdown <= 1))
+    }
+
+    // Counted as in `would_forward_to_parent`, zero means the next counted donation forwards.
+    // The forward itself happens after that donation lands, so the estimate is a lower bound.
+    // Every parent due on that same donation adds its share to the estimate.
+    pub fn next_forward(deps: Deps, env: Env) -> StdResult<Option<NextForwardResp>> {
+        let parents = parents(deps.storage)?;
+
+        let periods_remaining = match parents
+            .iter()
+            .map(|(_, countdown)| countdown.saturating_sub(1))
+            .min()
+        {
+            Some(periods_remaining) => periods_remaining,
+            None => return Ok(None),
+        };
+        let balance = held_balance(deps, &env)?;
+
+        let mut estimated_amount: Vec<Coin> = vec![];
+        for (parent_donation, _) in parents
+            .iter()
+            .filter(|(_, countdown)| countdown.saturating_sub(1) == periods_remaining)
+        {
+            for coin in parent_share(&balance, parent_donation) {
+                match estimated_amount.iter_mut().find(|c| c.denom == coin.denom) {
+                    Some(c) => c.amount += coin.amount,
+                    None => estimated_amount.push(coin),
+                }
+            }
+        }
+
+        Ok(Some(NextForwardResp {
+            periods_remaining,
+            estimated_amount,
+        }))
+    }
+
+    // Contracts migrated from before the echo was stored have none
+    pub fn init_params(deps: Deps) -> StdResult<Option<InstantiateMsg>> {
+        INIT_PARAMS.may_load(deps.storage)
+    }
+
+    // Sitting on a milestone means the next one is a whole milestone away
+    pub fn until_next_milestone(deps: Deps, env: Env) -> StdResult<Option<u64>> {
+        let milestone_size = match MILESTONE_SIZE.may_load(deps.storage)? {
+            Some(milestone_size) if milestone_size > 0 => milestone_size,
+            _ => return Ok(None),
+        };
+
+        let counter = total_counter(deps.storage, env.block.height)?;
+
+        Ok(Some(milestone_size - counter % milestone_size))
+    }
+
+    // Falls back to the owner when no destination was set
+    pub fn withdraw_destination(deps: Deps) -> StdResult<Addr> {
+        match WITHDRAW_DESTINATION.may_load(deps.storage)? {
+            Some(destination) => Ok(destination),
+            None => Ok(STATE.load(deps.storage)?.owner),
+        }
+    }
+
+    pub fn is_blocked(deps: Deps, addr: String) -> StdResult<bool> {
+        let addr = deps.api.addr_validate(&addr)?;
+
+        Ok(BLOCKED_DONORS.has(deps.storage, &addr))
+    }
+
+    pub fn recent_donations(deps: Deps, limit: Option<u32>) -> StdResult<Vec<DonationRecord>> {
+        let limit = (limit.unwrap_or(DEFAULT_LIMIT) as u64).min(RECENT_DONATIONS_SIZE);
+        let recorded = DONATIONS_RECORDED
+            .may_load(deps.storage)?
+            .unwrap_or_default();
+
+        (0..limit.min(recorded))
+            .map(|n| {
+                RECENT_DONATIONS.load(deps.storage, (recorded - 1 - n) % RECENT_DONATIONS_SIZE)
+            })
+            .collect()
+    }
+
+    pub fn cw20_policy(deps: Deps) -> StdResult<Option<Cw20PolicyResp>> {
+        let policy =
+            CW20_MINIMAL_DONATION
+                .may_load(deps.storage)?
+                .map(|(token, minimal_donation)| Cw20PolicyResp {
+                    token,
+                    minimal_donation,
+                });
+
+        Ok(policy)
+    }
+
+    // Migrated contracts have no record until their first exec
+    pub fn last_modified(deps: Deps) -> StdResult<u64> {
+        Ok(LAST_MODIFIED_HEIGHT
+            .may_load(deps.storage)?
+            .unwrap_or_default())
+    }
+
+    pub fn streak(deps: Deps, addr: String) -> StdResult<u64> {
+        let addr = deps.api.addr_validate(&addr)?;
+        let streak = STREAKS
+            .may_load(deps.storage, &addr)?
+            .map(|streak| streak.streak)
+            .unwrap_or_default();
+
+        Ok(streak)
+    }
+
+    // Denoms which were never donated report a zero amount
+    pub fn max_donation(deps: Deps, denom: String) -> StdResult<Coin> {
+        let amount = MAX_DONATION
+            .may_load(deps.storage, &denom)?
+            .unwrap_or_default();
+
+        Ok(Coin { denom, amount })
+    }
+
+    pub fn preview_migrated_state(
+        deps: Deps,
+        parent: Option<Parent>,
+    ) -> StdResult<MigratedStateResp> {
+        let state = super::preview_migrated_state(deps, parent.as_ref())?;
+
+        Ok(MigratedStateResp {
+            counter: state.counter,
+            minimal_donation: state.minimal_donation,
+            owner: state.owner,
+            donating_parent: state.donating_parent,
+        })
+    }
+
+    pub fn predecessor(deps: Deps) -> StdResult<Option<Addr>> {
+        PREDECESSOR.may_load(deps.storage)
+    }
+}
+
+// Define a new module called `exec`
+pub mod exec {
+    use cosmwasm_std::{
+        coins, to_binary, Addr, Coin, DepsMut, Env, Event, MessageInfo, Order, Response, StdResult,
+        Storage, SubMsg, Uint128, WasmMsg,
+    };
+    use cw2::query_contract_info;
+
+    use crate::{
+        error::ContractError,
+        msg::{
+            Cw20BalanceResponse, Cw20ExecuteMsg, Cw20QueryMsg, Cw20ReceiveMsg, DonationRecord,
+            ExecMsg, Payout, QueryMsg, RoleResp, WithdrawResp,
+        },
+        state::{
+            ScheduledDonation, State, StreakInfo, ACCEPT_FUNDS_WHEN_FREE, ALLOWED_DONORS,
+            BLOCKED_DONORS, CHILDREN, CW20_COUNTER, CW20_DONOR_COUNTS, CW20_MINIMAL_DONATION,
+            DONATIONS_RECORDED, DONOR_COUNTS, DONOR_TOTALS, EXTRA_PARENTS, GRANDFATHERED_UNTIL,
+            IDEMPOTENCY_KEYS, LAST_DONATION, LAST_DONOR, LAST_STIPEND_CLAIM, LAST_WITHDRAW, LOCK,
+            MAX_COUNTER, MAX_DONATION, MAX_FORWARD_DEPTH, MILESTONE_SIZE, MINIMAL_DONATION_BPS,
+            PARENT_DONATION, PAUSED, PREDECESSOR, RECEIPTS_ISSUED, RECENT_DONATIONS,
+            RECENT_DONATIONS_SIZE, REFUNDABLE, REFUND_EXCESS, REFUND_POOLS, REFUND_SHARES,
+            ROUNDS_COMPLETED, ROUND_DONOR_COUNTS, ROUND_SIZE, SCHEDULED_DONATIONS,
+            SCHEDULED_ESCROW, STATE, STIPEND, STREAKS, STREAK_WINDOW, TOLERANCE, TOTAL_DONATED,
+            TOTAL_WITHDRAWN, VANITY_COUNTER, WITHDRAW_COOLDOWN, WITHDRAW_DESTINATION,
+        },
+    };
+
+    use super::{
+        available_balance, decayed_counter, held_amount, held_balance, parent_share,
+        send_if_nonempty, snapshot_counter, take_withdraw_cut, total_counter, touch_counter,
+        CONTRACT_NAME, FORWARD_REPLY_ID,
+    };
+
+    // Merge coins sharing a denom, so every denom is evaluated and accounted once
+    // Deposits from before the escrow was tracked were never added, so releasing saturates
+    fn escrow(
+        storage: &mut dyn Storage,
+        denom: &str,
+        deposited: Uint128,
+        released: Uint128,
+    ) -> StdResult<()> {
+        SCHEDULED_ESCROW.update(storage, denom, |escrowed| -> StdResult<_> {
+            Ok((escrowed.unwrap_or_default() + deposited).saturating_sub(released))
+        })?;
+
+        Ok(())
+    }
+
+    fn normalize_funds(funds: &[Coin]) -> Vec<Coin> {
+        let mut normalized: Vec<Coin> = vec![];
+
+        for coin in funds {
+            match normalized.iter_mut().find(|c| c.denom == coin.denom) {
+                Some(c) => c.amount += coin.amount,
+                None => normalized.push(coin.clone()),
+            }
+        }
+
+        normalized
+    }
+
+    // Previously accepted denoms still count until their grace period expires,
+    // as long as the donation reaches the current minimal amount
+    fn grandfathered_donation(
+        storage: &dyn Storage,
+        height: u64,
+        funds: &[Coin],
+        minimal_amount: Uint128,
+    ) -> StdResult<bool> {
+        for coin in funds.iter().filter(|coin| coin.amount >= minimal_amount) {
+            if let Some(until) = GRANDFATHERED_UNTIL.may_load(storage, &coin.denom)? {
+                if height <= until {
+                    return Ok(true);
+                }
+            }
+        }
+
+        Ok(false)
+    }
+
+    // Native and cw20 donations are turned down the same way
+    fn check_donor(storage: &dyn Storage, donor: &Addr) -> Result<(), ContractError> {
+        if LOCK.may_load(storage)?.unwrap_or_default() {
+            return Err(ContractError::Reentrancy {});
+        }
+
+        if PAUSED.may_load(storage)?.unwrap_or_default() {
+            return Err(ContractError::Paused {});
+        }
+
+        // Blocking takes precedence over the allowed donors list
+        if BLOCKED_DONORS.has(storage, donor) {
+            return Err(ContractError::DonorBlocked {
+                donor: donor.to_string(),
+            });
+        }
+
+        if let Some(allowed_donors) = ALLOWED_DONORS.may_load(storage)? {
+            if !allowed_donors.contains(donor) {
+                return Err(ContractError::DonorNotAllowed {
+                    donor: donor.to_string(),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    // Funds attached to non-payable messages would be locked in the contract
+    fn nonpayable(info: &MessageInfo) -> Result<(), ContractError> {
+        if !info.funds.is_empty() {
+            return Err(ContractError::NonPayable {});
+        }
+
+        Ok(())
+    }
+
+    // Both withdraw paths share the cooldown, the check also records this withdraw
+    fn withdraw_cooldown(storage: &mut dyn Storage, env: &Env) -> Result<(), ContractError> {
+        if let Some(cooldown) = WITHDRAW_COOLDOWN.may_load(storage)? {
+            if let Some(last) = LAST_WITHDRAW.may_load(storage)? {
+                let available_at = last.plus_seconds(cooldown);
+                if env.block.time < available_at {
+                    return Err(ContractError::WithdrawCooldown {
+                        available_at: available_at.seconds(),
+                    });
+                }
+            }
+
+            LAST_WITHDRAW.save(storage, &env.block.time)?;
+        }
+
+        Ok(())
+    }
+
+    // Counterpart of `TOTAL_DONATED`, every coin paid out of the contract is added here,
+    // parent forwards included. Refunds take back from `TOTAL_DONATED` instead.
+    // Refunds can only come out of what is left of `held` once `paid` is sent, so every
+    // refund pool is cut down to that.
+    fn record_payout(
+        storage: &mut dyn Storage,
+        held: &[Coin],
+        paid: &[Coin],
+    ) -> Result<(), ContractError> {
+        for coin in paid.iter().filter(|coin| !coin.amount.is_zero()) {
+            TOTAL_WITHDRAWN.update(storage, &coin.denom, |total| {
+                total
+                    .unwrap_or_default()
+                    .checked_add(coin.amount)
+                    .map_err(|_| ContractError::AccountingOverflow {})
+            })?;
+        }
+
+        let pools = REFUND_POOLS
+            .range(storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        for (denom, mut pool) in pools {
+            let held = held
+                .iter()
+                .filter(|coin| coin.denom == denom)
+                .map(|coin| coin.amount)
+                .sum::<Uint128>();
+            let paid = paid
+                .iter()
+                .filter(|coin| coin.denom == denom)
+                .map(|coin| coin.amount)
+                .sum::<Uint128>();
+            let left = held.saturating_sub(paid);
+
+            if pool.amount <= left {
+                continue;
+            }
+
+            pool.amount = left;
+            if pool.amount.is_zero() {
+                pool.generation += 1;
+                pool.shares = Uint128::zero();
+            }
+
+            REFUND_POOLS.save(storage, &denom, &pool)?;
+        }
+
+        Ok(())
+    }
+
+    // New shares are priced at what the pool holds now, so they don't dilute earlier donors
+    fn add_refundable(storage: &mut dyn Storage, donor: &Addr, funds: &[Coin]) -> StdResult<()> {
+        for coin in funds.iter().filter(|coin| !coin.amount.is_zero()) {
+            let mut pool = REFUND_POOLS
+                .may_load(storage, &coin.denom)?
+                .unwrap_or_default();
+
+            let shares = if pool.shares.is_zero() {
+                coin.amount
+            } else {
+                coin.amount.multiply_ratio(pool.shares, pool.amount)
+            };
+
+            let key = (donor, coin.denom.as_str());
+            let owned = match REFUND_SHARES.may_load(storage, key)? {
+                Some((generation, owned)) if generation == pool.generation => owned,
+                _ => Uint128::zero(),
+            };
+            REFUND_SHARES.save(storage, key, &(pool.generation, owned + shares))?;
+
+            pool.shares += shares;
+            pool.amount += coin.amount;
+            REFUND_POOLS.save(storage, &coin.denom, &pool)?;
+        }
+
+        Ok(())
+    }
+
+    // Every coin of the reference donor's last donation has to be matched, in the same denom
+    pub fn donate_matching(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        reference_donor: String,
+    ) -> Result<Response, ContractError> {
+        let reference_donor = deps.api.addr_validate(&reference_donor)?;
+        // Matching nobody would let any donation through, so the reference has to exist
+        let required = LAST_DONATION
+            .may_load(deps.storage, &reference_donor)?
+            .ok_or_else(|| ContractError::UnknownDonor {
+                donor: reference_donor.to_string(),
+            })?;
+
+        let funds = normalize_funds(&info.funds);
+        for coin in &required {
+            let matched = funds
+                .iter()
+                .any(|fund| fund.denom == coin.denom && fund.amount >= coin.amount);
+
+            if !matched {
+                return Err(ContractError::DonationTooSmall {
+                    required: coin.to_string(),
+                });
+            }
+        }
+
+        donate(deps, env, info, None, None)
+    }
+
+    pub fn donate(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        forward_depth: Option<u8>,
+        idempotency_key: Option<String>,
+    ) -> Result<Response, ContractError> {
+        accept_donation(deps, env, info, forward_depth, idempotency_key, true)
+    }
+
+    // `attached` is false when the funds come out of a scheduled donation's escrow
+    fn accept_donation(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        forward_depth: Option<u8>,
+        idempotency_key: Option<String>,
+        attached: bool,
+    ) -> Result<Response, ContractError> {
+        check_donor(deps.storage, &info.sender)?;
+
+        // Keys are kept for good, the whole transaction reverts if the donation fails
+        if let Some(key) = idempotency_key {
+            if IDEMPOTENCY_KEYS.has(deps.storage, &key) {
+                return Err(ContractError::DuplicateDonation { key });
+            }
+
+            IDEMPOTENCY_KEYS.save(deps.storage, &key, &())?;
+        }
+
+        let mut state = STATE.load(deps.storage)?;
+        state.counter = decayed_counter(deps.storage, state.counter, env.block.height)?;
+
+        let mut resp = Response::new();
+
+        // Once the campaign goal is reached, donations are sent back to the donor
+        if let Some(max_counter) = MAX_COUNTER.may_load(deps.storage)? {
+            if total_counter(deps.storage, env.block.height)? >= max_counter {
+                resp = resp.add_messages(send_if_nonempty(&info.sender, info.funds));
+
+                resp = resp
+                    .add_attribute("action", "donate")
+                    .add_attribute("sender", info.sender.as_str())
+                    .add_attribute("counter", state.counter.to_string())
+                    .add_attribute("goal_reached", "true");
+
+                return Ok(resp);
+            }
+        }
+
+        let mut funds = normalize_funds(&info.funds);
+
+        let mut refund = vec![];
+
+        let bps = MINIMAL_DONATION_BPS
+            .may_load(deps.storage)?
+            .unwrap_or_default();
+
+        // A free contract may count funded donations without keeping the funds
+        if state.minimal_donation.amount.is_zero()
+            && !ACCEPT_FUNDS_WHEN_FREE
+                .may_load(deps.storage)?
+                .unwrap_or(true)
+        {
+            refund.append(&mut funds);
+        }
+
+        // Only the minimal donation is kept, the surplus goes back to the donor.
+        // In basis points there is no fixed amount to keep, so nothing is sent back.
+        if REFUND_EXCESS.may_load(deps.storage)?.unwrap_or_default()
+            && !bps
+            && !state.minimal_donation.amount.is_zero()
+        {
+            if let Some(coin) = funds.iter_mut().find(|coin| {
+                coin.denom == state.minimal_donation.denom
+                    && coin.amount > state.minimal_donation.amount
+            }) {
+                let excess = Coin {
+                    denom: coin.denom.clone(),
+                    amount: coin.amount - state.minimal_donation.amount,
+                };
+                coin.amount = state.minimal_donation.amount;
+                refund.push(excess);
+            }
+        }
+
+        resp = resp.add_messages(send_if_nonempty(&info.sender, refund.clone()));
+
+        for coin in &funds {
+            // Any donor's total is bounded by this one, so it is the only sum that needs checking
+            TOTAL_DONATED.update(deps.storage, &coin.denom, |total| {
+                total
+                    .unwrap_or_default()
+                    .checked_add(coin.amount)
+                    .map_err(|_| ContractError::AccountingOverflow {})
+            })?;
+
+            // Donors are ranked by value in the minimal donation denom only
+            if coin.denom == state.minimal_donation.denom && !coin.amount.is_zero() {
+                DONOR_TOTALS.update(deps.storage, &info.sender, |total| -> StdResult<_> {
+                    Ok(total.unwrap_or_default() + coin.amount)
+                })?;
+            }
+        }
+
+        if !funds.is_empty() {
+            LAST_DONATION.save(deps.storage, &info.sender, &funds)?;
+            add_refundable(deps.storage, &info.sender, &funds)?;
+        }
+
+        LAST_DONOR.save(deps.storage, &info.sender)?;
+
+        let threshold = if bps {
+            // Attached funds already left the donor's balance, so they are added back
+            let donated = funds
+                .iter()
+                .find(|coin| attached && coin.denom == state.minimal_donation.denom)
+                .map(|coin| coin.amount)
+                .unwrap_or_default();
+            let held = deps
+                .querier
+                .query_balance(&info.sender, &state.minimal_donation.denom)?
+                .amount;
+
+            (held + donated).multiply_ratio(state.minimal_donation.amount, 10_000u128)
+        } else {
+            state.minimal_donation.amount
+        };
+
+        // Fee-on-transfer denoms can arrive slightly short of what the donor sent
+        let threshold =
+            threshold.saturating_sub(TOLERANCE.may_load(deps.storage)?.unwrap_or_default());
+
+        let counted = state.minimal_donation.amount.is_zero()
+            || funds
+                .iter()
+                .any(|coin| coin.denom == state.minimal_donation.denom && coin.amount >= threshold)
+            || grandfathered_donation(deps.storage, env.block.height, &funds, threshold)?;
+
+        if counted {
+            state.counter += 1;
+            // Milestones and rounds are measured on the same value `Value` reports
+            let cw20_counter = CW20_COUNTER.may_load(deps.storage)?.unwrap_or_default();
+
+            resp = resp.add_event(issue_receipt(deps.storage, &info.sender, &funds)?);
+
+            // Checked before rounds can bring the counter back to zero
+            if let Some(milestone_size) = MILESTONE_SIZE.may_load(deps.storage)? {
+                let counter = state.counter + cw20_counter;
+                if milestone_size > 0 && counter % milestone_size == 0 {
+                    resp = resp.add_attribute("milestone", counter.to_string());
+                }
+            }
+
+            DONOR_COUNTS.update(deps.storage, &info.sender, |count| -> StdResult<_> {
+                Ok(count.unwrap_or_default() + 1)
+            })?;
+
+            // Recorded before the round below may complete, its reset takes this donation along
+            let round = ROUNDS_COMPLETED.may_load(deps.storage)?.unwrap_or_default();
+            ROUND_DONOR_COUNTS.update(deps.storage, &info.sender, |entry| -> StdResult<_> {
+                match entry {
+                    Some((entry_round, count)) if entry_round == round => Ok((round, count + 1)),
+                    _ => Ok((round, 1)),
+                }
+            })?;
+
+            if let Some(streak_window) = STREAK_WINDOW.may_load(deps.storage)? {
+                STREAKS.update(deps.storage, &info.sender, |streak| -> StdResult<_> {
+                    // A donation too long after the previous one starts a new streak
+                    let streak = match streak {
+                        Some(streak) if env.block.height - streak.last_height <= streak_window => {
+                            streak.streak + 1
+                        }
+                        _ => 1,
+                    };
+
+                    Ok(StreakInfo {
+                        last_height: env.block.height,
+                        streak,
+                    })
+                })?;
+            }
+
+            if let Some(round_size) = ROUND_SIZE.may_load(deps.storage)? {
+                if round_size > 0 && (state.counter + cw20_counter) % round_size == 0 {
+                    let round = ROUNDS_COMPLETED.may_load(deps.storage)?.unwrap_or_default() + 1;
+                    ROUNDS_COMPLETED.save(deps.storage, &round)?;
+                    state.counter = 0;
+                    CW20_COUNTER.save(deps.storage, &0)?;
+
+                    resp = resp.add_event(
+                        Event::new("round_completed").add_attribute("round", round.to_string()),
+                    );
+                }
+            }
+
+            for coin in &funds {
+                MAX_DONATION.update(deps.storage, &coin.denom, |max| -> StdResult<_> {
+                    Ok(max.unwrap_or_default().max(coin.amount))
+                })?;
+            }
+
+            let mut due_parents = vec![];
+
+            // A countdown already at zero is overdue, so the parent gets donated right away
+            if let Some(parent) = &mut state.donating_parent {
+                *parent = parent.saturating_sub(1);
+
+                if *parent == 0 {
+                    let parent_donation = PARENT_DONATION.load(deps.storage)?;
+                    *parent = parent_donation.donating_parent_period;
+                    due_parents.push(parent_donation);
+                }
+            }
+
+            // Every extra parent counts down its own period and starts over once it is due
+            let extra_parents = EXTRA_PARENTS
+                .range(deps.storage, None, None, Order::Ascending)
+                .collect::<StdResult<Vec<_>>>()?;
+            for (address, (parent_donation, countdown)) in extra_parents {
+                let countdown = match countdown.saturating_sub(1) {
+                    0 => {
+                        due_parents.push(parent_donation.clone());
+                        parent_donation.donating_parent_period
+                    }
+                    countdown => countdown,
+                };
+
+                EXTRA_PARENTS.save(deps.storage, &address, &(parent_donation, countdown))?;
+            }
+
+            if !due_parents.is_empty() {
+                let forward_depth = forward_depth.unwrap_or_default();
+                let depth_reached = match MAX_FORWARD_DEPTH.may_load(deps.storage)? {
+                    Some(max_forward_depth) => forward_depth >= max_forward_depth,
+                    None => false,
+                };
+
+                // Deep chains keep the parent part here instead of forwarding it any further
+                if depth_reached {
+                    resp = resp.add_attribute("forward_depth_reached", forward_depth.to_string());
+                } else {
+                    let balance: Vec<Coin> = held_balance(deps.as_ref(), &env)?
+                        .into_iter()
+                        .map(|mut coin| {
+                            // The refund is still in the balance until its message runs
+                            for refund in refund.iter().filter(|refund| refund.denom == coin.denom)
+                            {
+                                coin.amount = coin.amount.saturating_sub(refund.amount);
+                            }
+
+                            coin
+                        })
+                        .collect();
+
+                    let mut msgs = vec![];
+                    let mut forwarded = vec![];
+                    for parent_donation in due_parents {
+                        let funds = parent_share(&balance, &parent_donation);
+                        forwarded.extend(funds.iter().cloned());
+
+                        msgs.push(WasmMsg::Execute {
+                            contract_addr: parent_donation.address.to_string(),
+                            msg: to_binary(&ExecMsg::Donate {
+                                forward_depth: Some(forward_depth.saturating_add(1)),
+                                idempotency_key: None,
+                            })?,
+                            funds,
+                        });
+
+                        resp = resp.add_attribute(
+                            "donated_to_parent",
+                            parent_donation.address.to_string(),
+                        );
+                    }
+
+                    record_payout(deps.storage, &balance, &forwarded)?;
+
+                    // Locked until the last forwarding reply, so no parent can call back into `donate`
+                    LOCK.save(deps.storage, &true)?;
+
+                    if let Some(last) = msgs.pop() {
+                        resp = resp
+                            .add_messages(msgs)
+                            .add_submessage(SubMsg::reply_on_success(last, FORWARD_REPLY_ID));
+                    }
+                }
+            }
+
+            STATE.save(deps.storage, &state)?;
+            touch_counter(deps.storage, env.block.height)?;
+            snapshot_counter(deps.storage, env.block.height)?;
+        } else {
+            // Tell the donor why the donation didn't count, without a separate query
+            let reason = if funds.is_empty() {
+                "no_funds"
+            } else if funds
+                .iter()
+                .any(|coin| coin.denom == state.minimal_donation.denom)
+            {
+                "below_threshold"
+            } else {
+                "wrong_denom"
+            };
+
+            resp = resp.add_attribute("reason", reason);
+        }
+
+        record_donation(deps.storage, &env, &info.sender, funds, counted)?;
+
+        resp = resp
+            .add_attribute("action", "donate")
+            .add_attribute("sender", info.sender.as_str())
+            .add_attribute("counter", state.counter.to_string())
+            .add_attribute("counted", counted.to_string());
+
+        Ok(resp)
+    }
+
+    // Native and cw20 donations share the receipt numbering
+    fn issue_receipt(storage: &mut dyn Storage, donor: &Addr, amount: &[Coin]) -> StdResult<Event> {
+        // Ids start at 1, so the last one issued is also the number of receipts
+        let receipt_id = RECEIPTS_ISSUED.may_load(storage)?.unwrap_or_default() + 1;
+        RECEIPTS_ISSUED.save(storage, &receipt_id)?;
+
+        let amount = amount
+            .iter()
+            .map(Coin::to_string)
+            .collect::<Vec<_>>()
+            .join(",");
+
+        Ok(Event::new("receipt")
+            .add_attribute("receipt_id", receipt_id.to_string())
+            .add_attribute("donor", donor.as_str())
+            .add_attribute("amount", amount))
+    }
+
+    fn record_donation(
+        storage: &mut dyn Storage,
+        env: &Env,
+        donor: &Addr,
+        amount: Vec<Coin>,
+        counted: bool,
+    ) -> StdResult<()> {
+        // The oldest record is overwritten once the buffer is full
+        let recorded = DONATIONS_RECORDED.may_load(storage)?.unwrap_or_default();
+        RECENT_DONATIONS.save(
+            storage,
+            recorded % RECENT_DONATIONS_SIZE,
+            &DonationRecord {
+                donor: donor.clone(),
+                amount,
+                height: env.block.height,
+                counted,
+            },
+        )?;
+        DONATIONS_RECORDED.save(storage, &(recorded + 1))
+    }
+
+    // Called by the cw20 token, the donor is whoever sent the tokens
+    pub fn receive(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        msg: Cw20ReceiveMsg,
+    ) -> Result<Response, ContractError> {
+        nonpayable(&info)?;
+
+        let (token, minimal_donation) = match CW20_MINIMAL_DONATION.may_load(deps.storage)? {
+            Some(policy) if policy.0 == info.sender => policy,
+            _ => {
+                return Err(ContractError::Cw20NotAccepted {
+                    token: info.sender.into(),
+                })
+            }
+        };
+
+        let donor = deps.api.addr_validate(&msg.sender)?;
+        check_donor(deps.storage, &donor)?;
+
+        let native_counter = STATE.load(deps.storage)?.counter;
+        let native_counter = decayed_counter(deps.storage, native_counter, env.block.height)?;
+        let mut cw20_counter = CW20_COUNTER.may_load(deps.storage)?.unwrap_or_default();
+
+        // Tokens can't be sent back from here like native funds, so the whole `Send` fails instead
+        if let Some(max_counter) = MAX_COUNTER.may_load(deps.storage)? {
+            if native_counter + cw20_counter >= max_counter {
+                return Err(ContractError::GoalReached {});
+            }
+        }
+
+        // Recorded like native coins, with the token address standing in for the denom
+        let amount = coins(msg.amount.u128(), token.as_str());
+        let counted = msg.amount >= minimal_donation;
+        let mut resp = Response::new();
+
+        if counted {
+            cw20_counter += 1;
+            CW20_COUNTER.save(deps.storage, &cw20_counter)?;
+            snapshot_counter(deps.storage, env.block.height)?;
+            DONOR_COUNTS.update(deps.storage, &donor, |count| -> StdResult<_> {
+                Ok(count.unwrap_or_default() + 1)
+            })?;
+            CW20_DONOR_COUNTS.update(deps.storage, &donor, |count| -> StdResult<_> {
+                Ok(count.unwrap_or_default() + 1)
+            })?;
+
+            resp = resp.add_event(issue_receipt(deps.storage, &donor, &amount)?);
+        }
+
+        record_donation(deps.storage, &env, &donor, amount, counted)?;
+
+        let resp = resp
+            .add_attribute("action", "receive")
+            .add_attribute("sender", donor.as_str())
+            .add_attribute("token", token.as_str())
+            .add_attribute("counter", (native_counter + cw20_counter).to_string())
+            .add_attribute("counted", counted.to_string());
+
+        Ok(resp)
+    }
+
+    pub fn withdraw(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
+        nonpayable(&info)?;
+
+        let owner = STATE.load(deps.storage)?.owner;
+        if info.sender != owner {
+            return Err(ContractError::Unauthorized {
+                owner: owner.to_string(),
+            });
+        }
+
+        withdraw_cooldown(deps.storage, &env)?;
+
+        let held = held_balance(deps.as_ref(), &env)?;
+        let mut balance = available_balance(deps.as_ref(), &env)?;
+        let mut resp = Response::new();
+
+        // The cut is split off the balance, together they are all that is paid out
+        let paid = balance.clone();
+        if let Some((beneficiary, cut)) = take_withdraw_cut(deps.storage, &mut balance)? {
+            resp = resp.add_messages(send_if_nonempty(&beneficiary, cut));
+        }
+
+        record_payout(deps.storage, &held, &paid)?;
+
+        let data = to_binary(&WithdrawResp {
+            sent: balance.clone(),
+        })?;
+
+        let destination = WITHDRAW_DESTINATION
+            .may_load(deps.storage)?
+            .unwrap_or(owner);
+
+        // here msg.sender is this contract
+        let resp = resp
+            .add_messages(send_if_nonempty(&destination, balance))
+            .add_attribute("action", "withdraw")
+            .add_attribute("sender", info.sender.as_str())
+            .set_data(data);
+
+        Ok(resp)
+    }
+
+    pub fn set_withdraw_destination(
+        deps: DepsMut,
+        info: MessageInfo,
+        addr: String,
+    ) -> Result<Response, ContractError> {
+        nonpayable(&info)?;
+
+        let owner = STATE.load(deps.storage)?.owner;
+        if info.sender != owner {
+            return Err(ContractError::Unauthorized {
+                owner: owner.to_string(),
+            });
+        }
+
+        let destination = deps
+            .api
+            .addr_validate(&addr)
+            .map_err(|_| ContractError::InvalidReceiver { receiver: addr })?;
+        WITHDRAW_DESTINATION.save(deps.storage, &destination)?;
+
+        let resp = Response::new()
+            .add_attribute("action", "set_withdraw_destination")
+            .add_attribute("sender", info.sender.as_str())
+            .add_attribute("destination", destination.as_str());
+
+        Ok(resp)
+    }
+
+    // There is no swap here, the funds have to already be in `denom`
+    pub fn withdraw_as(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        denom: String,
+    ) -> Result<Response, ContractError> {
+        let owner = STATE.load(deps.storage)?.owner;
+        if info.sender != owner {
+            return Err(ContractError::Unauthorized {
+                owner: owner.to_string(),
+            });
+        }
+
+        let balance = held_balance(deps.as_ref(), &env)?;
+        if balance.iter().any(|coin| coin.denom != denom) {
+            return Err(ContractError::MixedDenoms { denom });
+        }
+
+        withdraw(deps, env, info)
+    }
+
+    pub fn withdraw_to(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        receiver: String,
+        funds: Vec<Coin>,
+    ) -> Result<Response, ContractError> {
+        nonpayable(&info)?;
+
+        let owner = STATE.load(deps.storage)?.owner;
+        if info.sender != owner {
+            return Err(ContractError::Unauthorized {
+                owner: owner.to_string(),
+            });
+        }
+
+        let receiver = deps
+            .api
+            .addr_validate(&receiver)
+            .map_err(|_| ContractError::InvalidReceiver { receiver })?;
+
+        withdraw_cooldown(deps.storage, &env)?;
+
+        // Query the current balance of the contract's address from the blockchain
+        let mut balance: Vec<Coin> = available_balance(deps.as_ref(), &env)?;
+
+        // Empty funds put no limit on it, so the whole balance goes to the receiver like in `withdraw`
+        if !funds.is_empty() {
+            // If funds were provided, iterate over each coin in the balance
+            for coin in &mut balance {
+                // Find the corresponding amount limit for the current coin from the provided funds (if any)
+                let limit = funds
+                    .iter()
+                    .find(|c| c.denom == coin.denom)
+                    .map(|c| c.amount)
+                    .unwrap_or(Uint128::zero());
+
+                // Set the coin amount to the minimum of the current amount and the limit (if there is a limit)
+                coin.amount = std::cmp::min(coin.amount, limit);
+            }
+
+            // Drop the denoms which were not requested, so no zero-amount coins are sent.
+            // Requested denoms the contract holds none of never make it into `balance` at all.
+            balance.retain(|coin| !coin.amount.is_zero());
+        }
+
+        let held = held_balance(deps.as_ref(), &env)?;
+        record_payout(deps.storage, &held, &balance)?;
+
+        let data = to_binary(&WithdrawResp {
+            sent: balance.clone(),
+        })?;
+
+        // here msg.sender is this contract
+        let resp = Response::new()
+            .add_messages(send_if_nonempty(&receiver, balance))
+            .add_attribute("action", "withdraw")
+            .add_attribute("sender", info.sender.as_str())
+            .set_data(data);
+
+        Ok(resp)
+    }
+
+    // Payouts to the same receiver are merged into a single bank message
+    pub fn withdraw_to_many(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        payouts: Vec<Payout>,
+    ) -> Result<Response, ContractError> {
+        nonpayable(&info)?;
+
+        let owner = STATE.load(deps.storage)?.owner;
+        if info.sender != owner {
+            return Err(ContractError::Unauthorized {
+                owner: owner.to_string(),
+            });
+        }
+
+        withdraw_cooldown(deps.storage, &env)?;
+
+        let mut merged: Vec<(Addr, Vec<Coin>)> = vec![];
+        for Payout { receiver, amount } in payouts {
+            let receiver = deps
+                .api
+                .addr_validate(&receiver)
+                .map_err(|_| ContractError::InvalidReceiver { receiver })?;
+
+            if amount.amount.is_zero() {
+                continue;
+            }
+
+            match merged.iter_mut().find(|(addr, _)| *addr == receiver) {
+                Some((_, coins)) => coins.push(amount),
+                None => merged.push((receiver, vec![amount])),
+            }
+        }
+
+        // Like `withdraw_to`, payouts only reach into what is available, served in the order given
+        let mut remaining = available_balance(deps.as_ref(), &env)?;
+
+        let mut msgs = vec![];
+        let mut paid = vec![];
+        for (receiver, coins) in merged {
+            let mut coins = normalize_funds(&coins);
+            for coin in &mut coins {
+                let left = remaining
+                    .iter_mut()
+                    .find(|left| left.denom == coin.denom)
+                    .map(|left| &mut left.amount);
+
+                coin.amount = match left {
+                    Some(left) => {
+                        let amount = std::cmp::min(coin.amount, *left);
+                        *left -= amount;
+                        amount
+                    }
+                    None => Uint128::zero(),
+                };
+            }
+            coins.retain(|coin| !coin.amount.is_zero());
+
+            paid.extend(coins.iter().cloned());
+            msgs.extend(send_if_nonempty(&receiver, coins));
+        }
+
+        let held = held_balance(deps.as_ref(), &env)?;
+        record_payout(deps.storage, &held, &paid)?;
+
+        let resp = Response::new()
+            .add_messages(msgs)
+            .add_attribute("action", "withdraw_to_many")
+            .add_attribute("sender", info.sender.as_str());
+
+        Ok(resp)
+    }
+
+    pub fn reset(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        counter: u64,
+    ) -> Result<Response, ContractError> {
+        nonpayable(&info)?;
+
+        let mut state = STATE.load(deps.storage)?;
+        if info.sender != state.owner {
+            return Err(ContractError::Unauthorized {
+                owner: state.owner.to_string(),
+            });
+        }
+
+        // The whole value is reset, so nothing stays behind in the cw20 counter
+        state.counter = counter;
+        STATE.save(deps.storage, &state)?;
+        CW20_COUNTER.save(deps.storage, &0)?;
+        touch_counter(deps.storage, env.block.height)?;
+        snapshot_counter(deps.storage, env.block.height)?;
+
+        let resp: Response = Response::new()
+            .add_attribute("action", "reset")
+            .add_attribute("sender", info.sender.as_str())
+            .add_attribute("counter", counter.to_string());
+
+        Ok(resp)
+    }
+
+    pub fn add_allowed_donor(
+        deps: DepsMut,
+        info: MessageInfo,
+        addr: String,
+    ) -> Result<Response, ContractError> {
+        nonpayable(&info)?;
+
+        let owner = STATE.load(deps.storage)?.owner;
+        if info.sender != owner {
+            return Err(ContractError::Unauthorized {
+                owner: owner.to_string(),
+            });
+        }
+
+        let addr = deps.api.addr_validate(&addr)?;
+
+        let mut allowed_donors = ALLOWED_DONORS.may_load(deps.storage)?.unwrap_or_default();
+        if !allowed_donors.contains(&addr) {
+            allowed_donors.push(addr.clone());
+        }
+        ALLOWED_DONORS.save(deps.storage, &allowed_donors)?;
+
+        let resp = Response::new()
+            .add_attribute("action", "add_allowed_donor")
+            .add_attribute("sender", info.sender.as_str())
+            .add_attribute("donor", addr.as_str());
+
+        Ok(resp)
+    }
+
+    pub fn remove_allowed_donor(
+        deps: DepsMut,
+        info: MessageInfo,
+        addr: String,
+    ) -> Result<Response, ContractError> {
+        nonpayable(&info)?;
+
+        let owner = STATE.load(deps.storage)?.owner;
+        if info.sender != owner {
+            return Err(ContractError::Unauthorized {
+                owner: owner.to_string(),
+            });
+        }
+
+        let addr = deps.api.addr_validate(&addr)?;
+
+        if let Some(mut allowed_donors) = ALLOWED_DONORS.may_load(deps.storage)? {
+            allowed_donors.retain(|donor| *donor != addr);
+            ALLOWED_DONORS.save(deps.storage, &allowed_donors)?;
+        }
+
+        let resp = Response::new()
+            .add_attribute("action", "remove_allowed_donor")
+            .add_attribute("sender", info.sender.as_str())
+            .add_attribute("donor", addr.as_str());
+
+        Ok(resp)
+    }
+
+    pub fn block_donor(
+        deps: DepsMut,
+        info: MessageInfo,
+        addr: String,
+    ) -> Result<Response, ContractError> {
+        nonpayable(&info)?;
+
+        let owner = STATE.load(deps.storage)?.owner;
+        if info.sender != owner {
+            return Err(ContractError::Unauthorized {
+                owner: owner.to_string(),
+            });
+        }
+
+        let addr = deps.api.addr_validate(&addr)?;
+        BLOCKED_DONORS.save(deps.storage, &addr, &())?;
+
+        let resp = Response::new()
+            .add_attribute("action", "block_donor")
+            .add_attribute("sender", info.sender.as_str())
+            .add_attribute("donor", addr.as_str());
+
+        Ok(resp)
+    }
+
+    pub fn unblock_donor(
+        deps: DepsMut,
+        info: MessageInfo,
+        addr: String,
+    ) -> Result<Response, ContractError> {
+        nonpayable(&info)?;
+
+        let owner = STATE.load(deps.storage)?.owner;
+        if info.sender != owner {
+            return Err(ContractError::Unauthorized {
+                owner: owner.to_string(),
+            });
+        }
+
+        let addr = deps.api.addr_validate(&addr)?;
+        BLOCKED_DONORS.remove(deps.storage, &addr);
+
+        let resp = Response::new()
+            .add_attribute("action", "unblock_donor")
+            .add_attribute("sender", info.sender.as_str())
+            .add_attribute("donor", addr.as_str());
+
+        Ok(resp)
+    }
+
+    pub fn set_parent_period(
+        deps: DepsMut,
+        info: MessageInfo,
+        period: u64,
+    ) -> Result<Response, ContractError> {
+        nonpayable(&info)?;
+
+        let mut state = STATE.load(deps.storage)?;
+        if info.sender != state.owner {
+            return Err(ContractError::Unauthorized {
+                owner: state.owner.to_string(),
+            });
+        }
+
+        let mut parent_donation = PARENT_DONATION
+            .may_load(deps.storage)?
+            .ok_or(ContractError::NoParent {})?;
+
+        if period == 0 {
+            return Err(ContractError::InvalidPeriod {});
+        }
+
+        parent_donation.donating_parent_period = period;
+        PARENT_DONATION.save(deps.storage, &parent_donation)?;
+
+        state.donating_parent = Some(period);
+        STATE.save(deps.storage, &state)?;
+
+        let resp = Response::new()
+            .add_attribute("action", "set_parent_period")
+            .add_attribute("sender", info.sender.as_str())
+            .add_attribute("period", period.to_string());
+
+        Ok(resp)
+    }
+
+    pub fn reset_parent_countdown(
+        deps: DepsMut,
+        info: MessageInfo,
+    ) -> Result<Response, ContractError> {
+        nonpayable(&info)?;
+
+        let mut state = STATE.load(deps.storage)?;
+        if info.sender != state.owner {
+            return Err(ContractError::Unauthorized {
+                owner: state.owner.to_string(),
+            });
+        }
+
+        let parent_donation = PARENT_DONATION
+            .may_load(deps.storage)?
+            .ok_or(ContractError::NoParent {})?;
+
+        state.donating_parent = Some(parent_donation.donating_parent_period);
+        STATE.save(deps.storage, &state)?;
+
+        let resp = Response::new()
+            .add_attribute("action", "reset_parent_countdown")
+            .add_attribute("sender", info.sender.as_str())
+            .add_attribute(
+                "countdown",
+                parent_donation.donating_parent_period.to_string(),
+            );
+
+        Ok(resp)
+    }
+
+    // The sender is asked who its parent is, so only actual children get listed
+    pub fn register_child(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+    ) -> Result<Response, ContractError> {
+        nonpayable(&info)?;
+
+        let not_child = || ContractError::NotChild {
+            addr: info.sender.to_string(),
+        };
+
+        let role: RoleResp = deps
+            .querier
+            .query_wasm_smart(&info.sender, &QueryMsg::Role {})
+            .map_err(|_| not_child())?;
+        if role
+            != (RoleResp::Child {
+                parent: env.contract.address,
+            })
+        {
+            return Err(not_child());
+        }
+
+        CHILDREN.save(deps.storage, &info.sender, &())?;
+
+        let resp = Response::new()
+            .add_attribute("action", "register_child")
+            .add_attribute("sender", info.sender.as_str());
+
+        Ok(resp)
+    }
+
+    pub fn remove_parent(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+        nonpayable(&info)?;
+
+        let mut state = STATE.load(deps.storage)?;
+        if info.sender != state.owner {
+            return Err(ContractError::Unauthorized {
+                owner: state.owner.to_string(),
+            });
+        }
+
+        PARENT_DONATION.remove(deps.storage);
+
+        let extra_parents = EXTRA_PARENTS
+            .keys(deps.storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        for address in extra_parents {
+            EXTRA_PARENTS.remove(deps.storage, &address);
+        }
+
+        state.donating_parent = None;
+        STATE.save(deps.storage, &state)?;
+
+        let resp = Response::new()
+            .add_attribute("action", "remove_parent")
+            .add_attribute("sender", info.sender.as_str());
+
+        Ok(resp)
+    }
+
+    pub fn evacuate(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        to: String,
+        donate: bool,
+    ) -> Result<Response, ContractError> {
+        nonpayable(&info)?;
+
+        let owner = STATE.load(deps.storage)?.owner;
+        if info.sender != owner {
+            return Err(ContractError::Unauthorized {
+                owner: owner.to_string(),
+            });
+        }
+
+        let to = deps.api.addr_validate(&to)?;
+        let balance = held_balance(deps.as_ref(), &env)?;
+        record_payout(deps.storage, &balance, &balance)?;
+
+        let mut resp = Response::new();
+
+        if donate {
+            resp = resp.add_message(WasmMsg::Execute {
+                contract_addr: to.to_string(),
+                msg: to_binary(&ExecMsg::Donate {
+                    forward_depth: None,
+                    idempotency_key: None,
+                })?,
+                funds: balance,
+            });
+        } else {
+            resp = resp.add_messages(send_if_nonempty(&to, balance));
+        }
+
+        resp = resp
+            .add_attribute("action", "evacuate")
+            .add_attribute("sender", info.sender.as_str())
+            .add_attribute("to", to.as_str());
+
+        Ok(resp)
+    }
+
+    // Tokens sent with a plain cw20 `Transfer` never reach `receive`, so only the owner can move them on
+    pub fn sweep_cw20(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        token: String,
+        to: String,
+    ) -> Result<Response, ContractError> {
+        nonpayable(&info)?;
+
+        let owner = STATE.load(deps.storage)?.owner;
+        if info.sender != owner {
+            return Err(ContractError::Unauthorized {
+                owner: owner.to_string(),
+            });
+        }
+
+        let token = deps.api.addr_validate(&token)?;
+        let to = deps.api.addr_validate(&to)?;
+
+        let Cw20BalanceResponse { balance } = deps.querier.query_wasm_smart(
+            &token,
+            &Cw20QueryMsg::Balance {
+                address: env.contract.address.into_string(),
+            },
+        )?;
+
+        let mut resp = Response::new();
+
+        if !balance.is_zero() {
+            resp = resp.add_message(WasmMsg::Execute {
+                contract_addr: token.to_string(),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: to.to_string(),
+                    amount: balance,
+                })?,
+                funds: vec![],
+            });
+        }
+
+        resp = resp
+            .add_attribute("action", "sweep_cw20")
+            .add_attribute("sender", info.sender.as_str())
+            .add_attribute("token", token.as_str())
+            .add_attribute("to", to.as_str())
+            .add_attribute("amount", balance.to_string());
+
+        Ok(resp)
+    }
+
+    pub fn set_minimal_donation(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        minimal_donation: Coin,
+        grace_period: Option<u64>,
+    ) -> Result<Response, ContractError> {
+        nonpayable(&info)?;
+
+        let mut state = STATE.load(deps.storage)?;
+        if info.sender != state.owner {
+            return Err(ContractError::Unauthorized {
+                owner: state.owner.to_string(),
+            });
+        }
+
+        let resp = Response::new()
+            .add_attribute("action", "set_minimal_donation")
+            .add_attribute("sender", info.sender.as_str())
+            .add_attribute("minimal_donation", minimal_donation.to_string());
+
+        change_minimal_donation(deps, &env, &mut state, minimal_donation, grace_period, resp)
+    }
+
+    // Shared by `SetMinimalDonation` and `UpdateConfig`, so a denom change is handled the same way
+    fn change_minimal_donation(
+        deps: DepsMut,
+        env: &Env,
+        state: &mut State,
+        minimal_donation: Coin,
+        grace_period: Option<u64>,
+        mut resp: Response,
+    ) -> Result<Response, ContractError> {
+        if minimal_donation.denom != state.minimal_donation.denom {
+            // Funds in the old denom stay in the contract until the owner sweeps or withdraws them
+            let stale_balance = Coin {
+                amount: held_amount(deps.as_ref(), env, &state.minimal_donation.denom)?,
+                denom: state.minimal_donation.denom.clone(),
+            };
+            resp = resp.add_attribute("stale_denom_balance", stale_balance.to_string());
+
+            if let Some(grace_period) = grace_period {
+                GRANDFATHERED_UNTIL.save(
+                    deps.storage,
+                    &state.minimal_donation.denom,
+                    &(env.block.height + grace_period),
+                )?;
+            }
+
+            // The new denom is accepted anyway
+            GRANDFATHERED_UNTIL.remove(deps.storage, &minimal_donation.denom);
+        }
+
+        state.minimal_donation = minimal_donation;
+        STATE.save(deps.storage, state)?;
+
+        Ok(resp)
+    }
+
+    pub fn recompute_stats(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+    ) -> Result<Response, ContractError> {
+        nonpayable(&info)?;
+
+        let state = STATE.load(deps.storage)?;
+        if info.sender != state.owner {
+            return Err(ContractError::Unauthorized {
+                owner: state.owner.to_string(),
+            });
+        }
+
+        // Donor counts hold native and cw20 donations alike, so they match the total counter
+        let donations = DONOR_COUNTS
+            .range(deps.storage, None, None, Order::Ascending)
+            .map(|entry| entry.map(|(_, count)| count))
+            .sum::<StdResult<u64>>()?;
+        let counter = total_counter(deps.storage, env.block.height)?;
+
+        let mut resp = Response::new()
+            .add_attribute("action", "recompute_stats")
+            .add_attribute("sender", info.sender.as_str())
+            .add_attribute("donations", donations.to_string())
+            .add_attribute("counter", counter.to_string());
+
+        // Report the mismatch instead of failing, this is a diagnostic only
+        if donations != counter {
+            let drift = counter as i128 - donations as i128;
+            resp = resp.add_attribute("drift", drift.to_string());
+        }
+
+        Ok(resp)
+    }
+
+    pub fn set_accept_funds_when_free(
+        deps: DepsMut,
+        info: MessageInfo,
+        accept: bool,
+    ) -> Result<Response, ContractError> {
+        nonpayable(&info)?;
+
+        let owner = STATE.load(deps.storage)?.owner;
+        if info.sender != owner {
+            return Err(ContractError::Unauthorized {
+                owner: owner.to_string(),
+            });
+        }
+
+        ACCEPT_FUNDS_WHEN_FREE.save(deps.storage, &accept)?;
+
+        let resp = Response::new()
+            .add_attribute("action", "set_accept_funds_when_free")
+            .add_attribute("sender", info.sender.as_str())
+            .add_attribute("accept", accept.to_string());
+
+        Ok(resp)
+    }
+
+    pub fn set_refundable(
+        deps: DepsMut,
+        info: MessageInfo,
+        refundable: bool,
+    ) -> Result<Response, ContractError> {
+        nonpayable(&info)?;
+
+        let owner = STATE.load(deps.storage)?.owner;
+        if info.sender != owner {
+            return Err(ContractError::Unauthorized {
+                owner: owner.to_string(),
+            });
+        }
+
+        REFUNDABLE.save(deps.storage, &refundable)?;
+
+        let resp = Response::new()
+            .add_attribute("action", "set_refundable")
+            .add_attribute("sender", info.sender.as_str())
+            .add_attribute("refundable", refundable.to_string());
+
+        Ok(resp)
+    }
+
+    // Parents and withdraws may already have taken part of the donations, each donor gets back
+    // their share of what is still held from them
+    pub fn request_refund(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+    ) -> Result<Response, ContractError> {
+        nonpayable(&info)?;
+
+        if !REFUNDABLE.may_load(deps.storage)?.unwrap_or_default() {
+            return Err(ContractError::NotRefundable {});
+        }
+
+        let shares = REFUND_SHARES
+            .prefix(&info.sender)
+            .range(deps.storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+
+        let mut refund = vec![];
+        for (denom, (generation, shares)) in shares {
+            REFUND_SHARES.remove(deps.storage, (&info.sender, &denom));
+
+            let mut pool = REFUND_POOLS.load(deps.storage, &denom)?;
+            if generation != pool.generation || pool.shares.is_zero() {
+                continue;
+            }
+
+            let amount = shares.multiply_ratio(pool.amount, pool.shares);
+            pool.amount -= amount;
+            pool.shares -= shares;
+            REFUND_POOLS.save(deps.storage, &denom, &pool)?;
+
+            if !amount.is_zero() {
+                refund.push(Coin { denom, amount });
+            }
+        }
+
+        if refund.is_empty() {
+            return Err(ContractError::NothingToRefund {});
+        }
+        LAST_DONATION.remove(deps.storage, &info.sender);
+
+        let mut state = STATE.load(deps.storage)?;
+
+        for coin in &refund {
+            TOTAL_DONATED.update(deps.storage, &coin.denom, |total| -> StdResult<_> {
+                Ok(total.unwrap_or_default().saturating_sub(coin.amount))
+            })?;
+
+            // Withdrawals shrink the refund, so part of the donor's total may stay behind
+            if coin.denom == state.minimal_donation.denom {
+                let total = DONOR_TOTALS
+                    .may_load(deps.storage, &info.sender)?
+                    .unwrap_or_default()
+                    .saturating_sub(coin.amount);
+                if total.is_zero() {
+                    DONOR_TOTALS.remove(deps.storage, &info.sender);
+                } else {
+                    DONOR_TOTALS.save(deps.storage, &info.sender, &total)?;
+                }
+            }
+        }
+
+        // Every native donation of this donor which counted is taken back, cw20 tokens aren't refunded
+        let cw20_count = CW20_DONOR_COUNTS
+            .may_load(deps.storage, &info.sender)?
+            .unwrap_or_default();
+        let count = DONOR_COUNTS
+            .may_load(deps.storage, &info.sender)?
+            .unwrap_or_default()
+            .saturating_sub(cw20_count);
+        if cw20_count > 0 {
+            DONOR_COUNTS.save(deps.storage, &info.sender, &cw20_count)?;
+        } else {
+            DONOR_COUNTS.remove(deps.storage, &info.sender);
+        }
+
+        // Donations of completed rounds were already reset away, only those of the current one remain
+        let round = ROUNDS_COMPLETED.may_load(deps.storage)?.unwrap_or_default();
+        let count = match ROUND_DONOR_COUNTS.may_load(deps.storage, &info.sender)? {
+            Some((entry_round, round_count)) if entry_round == round => count.min(round_count),
+            Some(_) => 0,
+            None => count,
         };
+        ROUND_DONOR_COUNTS.remove(deps.storage, &info.sender);
+
+        state.counter =
+            decayed_counter(deps.storage, state.counter, env.block.height)?.saturating_sub(count);
+        STATE.save(deps.storage, &state)?;
+        touch_counter(deps.storage, env.block.height)?;
+        snapshot_counter(deps.storage, env.block.height)?;
+
+        let refunded = refund
+            .iter()
+            .map(Coin::to_string)
+            .collect::<Vec<_>>()
+            .join(",");
 
         let resp = Response::new()
-            .add_message(bank_msg)
-            .add_attribute("action", "withdraw")
+            .add_messages(send_if_nonempty(&info.sender, refund))
+            .add_attribute("action", "request_refund")
+            .add_attribute("sender", info.sender.as_str())
+            .add_attribute("refunded", refunded)
+            .add_attribute("counter", state.counter.to_string());
+
+        Ok(resp)
+    }
+
+    pub fn update_config(
+        mut deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        minimal_donation: Option<Coin>,
+        paused: Option<bool>,
+        max_counter: Option<u64>,
+    ) -> Result<Response, ContractError> {
+        nonpayable(&info)?;
+
+        let mut state = STATE.load(deps.storage)?;
+        if info.sender != state.owner {
+            return Err(ContractError::Unauthorized {
+                owner: state.owner.to_string(),
+            });
+        }
+
+        let mut resp = Response::new()
+            .add_attribute("action", "update_config")
             .add_attribute("sender", info.sender.as_str());
 
+        if let Some(minimal_donation) = minimal_donation {
+            resp = resp.add_attribute("minimal_donation", minimal_donation.to_string());
+            resp = change_minimal_donation(
+                deps.branch(),
+                &env,
+                &mut state,
+                minimal_donation,
+                None,
+                resp,
+            )?;
+        }
+
+        if let Some(paused) = paused {
+            PAUSED.save(deps.storage, &paused)?;
+            resp = resp.add_attribute("paused", paused.to_string());
+        }
+
+        if let Some(max_counter) = max_counter {
+            // Same rule as at instantiation, a goal already passed can't be reached
+            if total_counter(deps.storage, env.block.height)? > max_counter {
+                return Err(ContractError::CounterTooHigh { max: max_counter });
+            }
+
+            MAX_COUNTER.save(deps.storage, &max_counter)?;
+            resp = resp.add_attribute("max_counter", max_counter.to_string());
+        }
+
         Ok(resp)
     }
 
-    pub fn withdraw_to(
+    // The deposit stays in this contract until the scheduled donations use it up
+    pub fn schedule_donation(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        every_blocks: u64,
+        amount: Coin,
+    ) -> Result<Response, ContractError> {
+        // A zero interval would let the whole deposit be donated within a single block
+        if every_blocks == 0 {
+            return Err(ContractError::InvalidPeriod {});
+        }
+
+        let mut deposit = Uint128::zero();
+        for coin in &info.funds {
+            if coin.denom != amount.denom {
+                return Err(ContractError::InvalidDeposit {
+                    denom: amount.denom,
+                });
+            }
+
+            deposit += coin.amount;
+        }
+
+        escrow(deps.storage, &amount.denom, deposit, Uint128::zero())?;
+
+        let mut resp = Response::new();
+
+        // Rescheduling keeps the deposit left in the same denom, and sends back any other
+        if let Some(previous) = SCHEDULED_DONATIONS.may_load(deps.storage, &info.sender)? {
+            if previous.amount.denom == amount.denom {
+                deposit += previous.deposit;
+            } else {
+                escrow(
+                    deps.storage,
+                    &previous.amount.denom,
+                    Uint128::zero(),
+                    previous.deposit,
+                )?;
+
+                let previous_deposit = Coin {
+                    denom: previous.amount.denom,
+                    amount: previous.deposit,
+                };
+                resp = resp.add_messages(send_if_nonempty(&info.sender, vec![previous_deposit]));
+            }
+        }
+
+        let next_height = env.block.height + every_blocks;
+        SCHEDULED_DONATIONS.save(
+            deps.storage,
+            &info.sender,
+            &ScheduledDonation {
+                every_blocks,
+                amount,
+                deposit,
+                next_height,
+            },
+        )?;
+
+        let resp = resp
+            .add_attribute("action", "schedule_donation")
+            .add_attribute("sender", info.sender.as_str())
+            .add_attribute("deposit", deposit.to_string())
+            .add_attribute("next_height", next_height.to_string());
+
+        Ok(resp)
+    }
+
+    // Applies the scheduled donation as if the donor sent it from their deposit
+    pub fn execute_scheduled(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        donor: String,
+    ) -> Result<Response, ContractError> {
+        nonpayable(&info)?;
+
+        let donor = deps.api.addr_validate(&donor)?;
+        let mut scheduled = SCHEDULED_DONATIONS
+            .may_load(deps.storage, &donor)?
+            .ok_or(ContractError::NoScheduledDonation {})?;
+
+        if env.block.height < scheduled.next_height {
+            return Err(ContractError::ScheduledDonationNotDue {
+                available_at: scheduled.next_height,
+            });
+        }
+
+        scheduled.deposit = scheduled
+            .deposit
+            .checked_sub(scheduled.amount.amount)
+            .map_err(|_| ContractError::InsufficientDeposit {})?;
+        scheduled.next_height = env.block.height + scheduled.every_blocks;
+        SCHEDULED_DONATIONS.save(deps.storage, &donor, &scheduled)?;
+
+        // Only what is still in the bank balance can be donated
+        let balance = deps
+            .querier
+            .query_balance(&env.contract.address, &scheduled.amount.denom)?
+            .amount;
+        let escrowed = SCHEDULED_ESCROW
+            .may_load(deps.storage, &scheduled.amount.denom)?
+            .unwrap_or_default();
+        if balance < escrowed.max(scheduled.amount.amount) {
+            return Err(ContractError::InsufficientDeposit {});
+        }
+
+        // The applied amount leaves the escrow and becomes a regular donation
+        escrow(
+            deps.storage,
+            &scheduled.amount.denom,
+            Uint128::zero(),
+            scheduled.amount.amount,
+        )?;
+
+        let donation = MessageInfo {
+            sender: donor,
+            funds: vec![scheduled.amount],
+        };
+        let resp = accept_donation(deps, env, donation, None, None, false)?
+            .add_attribute("cranker", info.sender.as_str());
+
+        Ok(resp)
+    }
+
+    // Works while paused as well, the deposit is still the donor's
+    pub fn cancel_scheduled_donation(
+        deps: DepsMut,
+        info: MessageInfo,
+    ) -> Result<Response, ContractError> {
+        nonpayable(&info)?;
+
+        let scheduled = SCHEDULED_DONATIONS
+            .may_load(deps.storage, &info.sender)?
+            .ok_or(ContractError::NoScheduledDonation {})?;
+        SCHEDULED_DONATIONS.remove(deps.storage, &info.sender);
+
+        escrow(
+            deps.storage,
+            &scheduled.amount.denom,
+            Uint128::zero(),
+            scheduled.deposit,
+        )?;
+
+        let deposit = Coin {
+            denom: scheduled.amount.denom,
+            amount: scheduled.deposit,
+        };
+
+        let resp = Response::new()
+            .add_messages(send_if_nonempty(&info.sender, vec![deposit.clone()]))
+            .add_attribute("action", "cancel_scheduled_donation")
+            .add_attribute("sender", info.sender.as_str())
+            .add_attribute("deposit", deposit.to_string());
+
+        Ok(resp)
+    }
+
+    // The funds only land in the balance, the counter and donor stats stay as they are
+    pub fn fund(info: MessageInfo) -> Result<Response, ContractError> {
+        let resp = Response::new()
+            .add_attribute("action", "fund")
+            .add_attribute("sender", info.sender.as_str());
+
+        Ok(resp)
+    }
+
+    // Tracked funds still in the contract are what was donated minus what was paid out, refunds
+    // already taken off the donated total. Anything held above that is untracked, like funds from
+    // `Fund`. Scheduled-donation deposits are left out, as they still belong to their donors.
+    pub fn reconcile(
         deps: DepsMut,
         env: Env,
         info: MessageInfo,
-        receiver: String,
-        funds: Vec<Coin>,
     ) -> Result<Response, ContractError> {
+        nonpayable(&info)?;
+
+        let mut state = STATE.load(deps.storage)?;
+        if info.sender != state.owner {
+            return Err(ContractError::Unauthorized {
+                owner: state.owner.to_string(),
+            });
+        }
+
+        let denom = &state.minimal_donation.denom;
+        let balance = held_amount(deps.as_ref(), &env, denom)?;
+        let tracked = TOTAL_DONATED
+            .may_load(deps.storage, denom)?
+            .unwrap_or_default();
+        let withdrawn = TOTAL_WITHDRAWN
+            .may_load(deps.storage, denom)?
+            .unwrap_or_default();
+        let surplus = balance.saturating_sub(tracked.saturating_sub(withdrawn));
+
+        // Every minimal donation in the surplus counts once, the remainder stays untracked
+        // so it can still add up with later surplus. A free contract counts it all as one.
+        let (donations, adopted) = if surplus.is_zero() {
+            (0, Uint128::zero())
+        } else if state.minimal_donation.amount.is_zero() {
+            (1, surplus)
+        } else {
+            let donations = surplus / state.minimal_donation.amount;
+            (
+                donations.u128() as u64,
+                donations * state.minimal_donation.amount,
+            )
+        };
+
+        if !adopted.is_zero() {
+            TOTAL_DONATED.save(deps.storage, denom, &(tracked + adopted))?;
+        }
+
+        if donations > 0 {
+            state.counter =
+                decayed_counter(deps.storage, state.counter, env.block.height)? + donations;
+            STATE.save(deps.storage, &state)?;
+            touch_counter(deps.storage, env.block.height)?;
+            snapshot_counter(deps.storage, env.block.height)?;
+        }
+
+        let resp = Response::new()
+            .add_attribute("action", "reconcile")
+            .add_attribute("sender", info.sender.as_str())
+            .add_attribute("adopted", adopted.to_string())
+            .add_attribute("counter", state.counter.to_string());
+
+        Ok(resp)
+    }
+
+    pub fn increment_vanity(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+        nonpayable(&info)?;
+
         let owner = STATE.load(deps.storage)?.owner;
         if info.sender != owner {
             return Err(ContractError::Unauthorized {
@@ -263,56 +3051,245 @@ pub mod exec {
             });
         }
 
-        // Query the current balance of the contract's address from the blockchain
-        let mut balance: Vec<Coin> = deps.querier.query_all_balances(&env.contract.address)?;
+        let counter = VANITY_COUNTER.may_load(deps.storage)?.unwrap_or_default() + 1;
+        VANITY_COUNTER.save(deps.storage, &counter)?;
 
-        // Check if there are any funds provided in the message info
-        if !funds.is_empty() {
-            // If funds were provided, iterate over each coin in the balance
-            for coin in &mut balance {
-                // Find the corresponding amount limit for the current coin from the provided funds (if any)
-                let limit = funds
-                    .iter()
-                    .find(|c| c.denom == coin.denom)
-                    .map(|c| c.amount)
-                    .unwrap_or(Uint128::zero());
+        let resp = Response::new()
+            .add_attribute("action", "increment_vanity")
+            .add_attribute("sender", info.sender.as_str())
+            .add_attribute("counter", counter.to_string());
 
-                // Set the coin amount to the minimum of the current amount and the limit (if there is a limit)
-                coin.amount = std::cmp::min(coin.amount, limit);
+        Ok(resp)
+    }
+
+    pub fn claim_stipend(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+    ) -> Result<Response, ContractError> {
+        nonpayable(&info)?;
+
+        let owner = STATE.load(deps.storage)?.owner;
+        if info.sender != owner {
+            return Err(ContractError::Unauthorized {
+                owner: owner.to_string(),
+            });
+        }
+
+        let (stipend, period) = STIPEND
+            .may_load(deps.storage)?
+            .ok_or(ContractError::NoStipend {})?;
+
+        if let Some(last_claim) = LAST_STIPEND_CLAIM.may_load(deps.storage)? {
+            let available_at = last_claim + period;
+            if env.block.height < available_at {
+                return Err(ContractError::StipendNotReady { available_at });
             }
         }
 
-        // here msg.sender is this contract
-        let bank_msg = BankMsg::Send {
-            to_address: receiver,
-            amount: funds,
+        LAST_STIPEND_CLAIM.save(deps.storage, &env.block.height)?;
+
+        // Like the tip, it can't reach into the scheduled-donation deposits
+        let held = held_balance(deps.as_ref(), &env)?;
+        let stipend = Coin {
+            amount: stipend
+                .amount
+                .min(held_amount(deps.as_ref(), &env, &stipend.denom)?),
+            denom: stipend.denom,
         };
+        record_payout(deps.storage, &held, std::slice::from_ref(&stipend))?;
 
         let resp = Response::new()
-            .add_message(bank_msg)
-            .add_attribute("action", "withdraw")
+            .add_messages(send_if_nonempty(&owner, vec![stipend.clone()]))
+            .add_attribute("action", "claim_stipend")
+            .add_attribute("sender", info.sender.as_str())
+            .add_attribute("stipend", stipend.to_string());
+
+        Ok(resp)
+    }
+
+    // The tip is clamped to what the contract holds in its denom
+    pub fn tip_last_donor(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        amount: Coin,
+    ) -> Result<Response, ContractError> {
+        nonpayable(&info)?;
+
+        let owner = STATE.load(deps.storage)?.owner;
+        if info.sender != owner {
+            return Err(ContractError::Unauthorized {
+                owner: owner.to_string(),
+            });
+        }
+
+        let last_donor = LAST_DONOR
+            .may_load(deps.storage)?
+            .ok_or(ContractError::NoLastDonor {})?;
+
+        let held = held_balance(deps.as_ref(), &env)?;
+        let balance = held_amount(deps.as_ref(), &env, &amount.denom)?;
+        let tip = Coin {
+            amount: amount.amount.min(balance),
+            denom: amount.denom,
+        };
+
+        record_payout(deps.storage, &held, std::slice::from_ref(&tip))?;
+
+        let resp = Response::new().add_messages(send_if_nonempty(&last_donor, vec![tip.clone()]));
+
+        let resp = resp
+            .add_attribute("action", "tip_last_donor")
+            .add_attribute("sender", info.sender.as_str())
+            .add_attribute("donor", last_donor.as_str())
+            .add_attribute("tip", tip.to_string());
+
+        Ok(resp)
+    }
+
+    // No owner check, reaching the goal is what authorizes it
+    pub fn finalize_campaign(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+    ) -> Result<Response, ContractError> {
+        nonpayable(&info)?;
+
+        let state = STATE.load(deps.storage)?;
+        let counter = total_counter(deps.storage, env.block.height)?;
+        match MAX_COUNTER.may_load(deps.storage)? {
+            Some(max_counter) if counter >= max_counter => (),
+            _ => return Err(ContractError::GoalNotReached {}),
+        }
+
+        PAUSED.save(deps.storage, &true)?;
+
+        let balance = held_balance(deps.as_ref(), &env)?;
+        record_payout(deps.storage, &balance, &balance)?;
+
+        let resp = Response::new().add_messages(send_if_nonempty(&state.owner, balance));
+
+        let resp = resp
+            .add_attribute("action", "finalize_campaign")
             .add_attribute("sender", info.sender.as_str());
 
         Ok(resp)
     }
 
-    pub fn reset(
+    // Funds go over with a plain bank send, so the successor doesn't count them as donations.
+    // Only the funds and the counter move, donor stats and configuration stay with this contract.
+    pub fn handover(
         deps: DepsMut,
+        env: Env,
         info: MessageInfo,
-        counter: u64,
+        successor: String,
     ) -> Result<Response, ContractError> {
-        let mut state = STATE.load(deps.storage)?;
+        nonpayable(&info)?;
+
+        let state = STATE.load(deps.storage)?;
         if info.sender != state.owner {
             return Err(ContractError::Unauthorized {
                 owner: state.owner.to_string(),
             });
         }
 
+        let invalid_successor = || ContractError::InvalidSuccessor {
+            successor: successor.clone(),
+        };
+
+        let successor_addr = deps
+            .api
+            .addr_validate(&successor)
+            .map_err(|_| invalid_successor())?;
+        let successor_version =
+            query_contract_info(&deps.querier, &successor_addr).map_err(|_| invalid_successor())?;
+        if successor_version.contract != CONTRACT_NAME || successor_addr == env.contract.address {
+            return Err(invalid_successor());
+        }
+
+        // Checked up front, so the owner isn't left with a failed `ImportCounter` to decode.
+        // Successors too old to answer can't accept the handover either.
+        let predecessor: Option<Addr> = deps
+            .querier
+            .query_wasm_smart(&successor_addr, &QueryMsg::Predecessor {})
+            .unwrap_or_default();
+        if predecessor.as_ref() != Some(&env.contract.address) {
+            return Err(ContractError::SuccessorNotReady { successor });
+        }
+
+        // The successor has a single counter, so the cw20 donations are carried over in it
+        let counter = total_counter(deps.storage, env.block.height)?;
+        // Scheduled-donation deposits stay here, so their donors can still cancel them
+        let balance = held_balance(deps.as_ref(), &env)?;
+        record_payout(deps.storage, &balance, &balance)?;
+
+        PAUSED.save(deps.storage, &true)?;
+
+        let resp = Response::new()
+            .add_messages(send_if_nonempty(&successor_addr, balance))
+            .add_message(WasmMsg::Execute {
+                contract_addr: successor_addr.to_string(),
+                msg: to_binary(&ExecMsg::ImportCounter { counter })?,
+                funds: vec![],
+            })
+            .add_attribute("action", "handover")
+            .add_attribute("sender", info.sender.as_str())
+            .add_attribute("successor", successor_addr.as_str())
+            .add_attribute("counter", counter.to_string());
+
+        Ok(resp)
+    }
+
+    pub fn set_predecessor(
+        deps: DepsMut,
+        info: MessageInfo,
+        addr: String,
+    ) -> Result<Response, ContractError> {
+        nonpayable(&info)?;
+
+        let owner = STATE.load(deps.storage)?.owner;
+        if info.sender != owner {
+            return Err(ContractError::Unauthorized {
+                owner: owner.to_string(),
+            });
+        }
+
+        let predecessor = deps.api.addr_validate(&addr)?;
+        PREDECESSOR.save(deps.storage, &predecessor)?;
+
+        let resp = Response::new()
+            .add_attribute("action", "set_predecessor")
+            .add_attribute("sender", info.sender.as_str())
+            .add_attribute("predecessor", predecessor.as_str());
+
+        Ok(resp)
+    }
+
+    // The predecessor can only import once, a second handover needs the owner's approval again
+    pub fn import_counter(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        counter: u64,
+    ) -> Result<Response, ContractError> {
+        nonpayable(&info)?;
+
+        if PREDECESSOR.may_load(deps.storage)?.as_ref() != Some(&info.sender) {
+            return Err(ContractError::NotPredecessor {
+                sender: info.sender.to_string(),
+            });
+        }
+        PREDECESSOR.remove(deps.storage);
+
+        let mut state = STATE.load(deps.storage)?;
         state.counter = counter;
         STATE.save(deps.storage, &state)?;
+        touch_counter(deps.storage, env.block.height)?;
+        snapshot_counter(deps.storage, env.block.height)?;
 
-        let resp: Response = Response::new()
-            .add_attribute("action", "reset")
+        let resp = Response::new()
+            .add_attribute("action", "import_counter")
             .add_attribute("sender", info.sender.as_str())
             .add_attribute("counter", counter.to_string());
 
@@ -1,6 +1,11 @@
 use cosmwasm_std::StdError;
 use thiserror::Error;
 
+// cw2 reports a missing version as a plain `StdError`, this keeps it apart from other storage errors
+#[derive(Error, Debug, PartialEq)]
+#[error(transparent)]
+pub struct VersionError(#[from] pub StdError);
+
 #[derive(Error, Debug, PartialEq)]
 pub enum ContractError {
     #[error("{0}")]
@@ -9,9 +14,119 @@ pub enum ContractError {
     #[error("Unauthorized - only {owner} can call it")]
     Unauthorized { owner: String },
 
+    #[error("Contract version unavailable: {0}")]
+    Cw2(#[from] VersionError),
+
     #[error("Invalid contract to migrate from: {contract}")]
     InvalidContract { contract: String },
 
     #[error("Unsupported contract version for migration: {version}")]
     InvalidContractVersion { version: String },
+
+    #[error("Already migrated - the storage already holds the current state layout")]
+    AlreadyMigrated {},
+
+    #[error("Counter too high - it is already above the goal of {max}")]
+    CounterTooHigh { max: u64 },
+
+    #[error("Donor not allowed - {donor} is not on the allowed donors list")]
+    DonorNotAllowed { donor: String },
+
+    #[error("Donor blocked - {donor} is not allowed to donate")]
+    DonorBlocked { donor: String },
+
+    #[error("Non-payable - this message does not accept funds")]
+    NonPayable {},
+
+    #[error("No parent - this contract doesn't donate to a parent")]
+    NoParent {},
+
+    #[error("Invalid successor - {successor} is not a counting contract")]
+    InvalidSuccessor { successor: String },
+
+    #[error("Not the predecessor - {sender} wasn't set to hand over to this contract")]
+    NotPredecessor { sender: String },
+
+    #[error("Successor not ready - {successor} hasn't set this contract as its predecessor")]
+    SuccessorNotReady { successor: String },
+
+    #[error("Not a child - {addr} doesn't donate to this contract")]
+    NotChild { addr: String },
+
+    #[error("Invalid period - it has to be greater than zero")]
+    InvalidPeriod {},
+
+    #[error("Invalid parent parts - together they can't be more than the whole balance")]
+    InvalidParentParts {},
+
+    #[error("Invalid withdraw cut - it can't be more than the whole balance")]
+    InvalidWithdrawCut {},
+
+    #[error("Reentrancy - donations are locked while forwarding to the parent")]
+    Reentrancy {},
+
+    #[error("Duplicate donation - the idempotency key {key} was already used")]
+    DuplicateDonation { key: String },
+
+    #[error("Paused - donations are not accepted at the moment")]
+    Paused {},
+
+    #[error("Donation too small - it has to match at least {required}")]
+    DonationTooSmall { required: String },
+
+    #[error("Accounting overflow - the donated total doesn't fit in its counter")]
+    AccountingOverflow {},
+
+    #[error("Withdraw cooldown - the next withdraw is available at {available_at} seconds")]
+    WithdrawCooldown { available_at: u64 },
+
+    #[error("No stipend - this contract doesn't pay a stipend")]
+    NoStipend {},
+
+    #[error("Stipend not ready - it can be claimed again at height {available_at}")]
+    StipendNotReady { available_at: u64 },
+
+    #[error("No last donor - nobody donated yet")]
+    NoLastDonor {},
+
+    #[error("Unknown donor - {donor} didn't donate yet")]
+    UnknownDonor { donor: String },
+
+    #[error("Not refundable - the owner didn't open refunds for this campaign")]
+    NotRefundable {},
+
+    #[error("Nothing to refund - none of your donations is left in the contract")]
+    NothingToRefund {},
+
+    #[error(
+        "Goal not reached - the campaign can only be finalized once the counter reaches its goal"
+    )]
+    GoalNotReached {},
+
+    #[error("Mixed denoms - the balance has to be only in {denom} to be withdrawn")]
+    MixedDenoms { denom: String },
+
+    #[error("Invalid deposit - scheduled donations can only be funded in {denom}")]
+    InvalidDeposit { denom: String },
+
+    #[error("No scheduled donation - this donor didn't schedule any donation")]
+    NoScheduledDonation {},
+
+    #[error("Scheduled donation not due - it can be applied at height {available_at}")]
+    ScheduledDonationNotDue { available_at: u64 },
+
+    #[error("Insufficient deposit - what is left doesn't cover the scheduled donation")]
+    InsufficientDeposit {},
+
+    #[error("Cw20 not accepted - {token} is not counted as a donation here")]
+    Cw20NotAccepted { token: String },
+
+    #[error("Goal reached - no more donations are counted")]
+    GoalReached {},
+
+    #[error("Unknown reply id: {id}")]
+    UnknownReply { id: u64 },
+
+    #[error("Invalid receiver address: {receiver}")]
+    InvalidReceiver { receiver: String },
 }
@@ -1,6 +1,9 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
-use cosmwasm_std::{to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult};
+use cosmwasm_std::{
+    to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Reply, Response, StdResult,
+};
+use cw2::get_contract_version;
 use error::ContractError;
 
 // Import the `contract` module, the `msg`, and the `state` module from the current crate
@@ -17,26 +20,69 @@ mod state;
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     msg: msg::InstantiateMsg,
-) -> StdResult<Response> {
-    contract::instantiate(deps, info, msg.counter, msg.minimal_donation, msg.parent)
+) -> Result<Response, ContractError> {
+    contract::instantiate(deps, env, info, msg)
 }
 
 // Define the `query` entry point function, which is called when a read-only operation is performed on the contract
 // This attribute is used to mark the function as an entry point for the smart contract.
 // It is conditionally compiled with a feature flag to prevent it from being included in the library version of the code.
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: msg::QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: msg::QueryMsg) -> StdResult<Binary> {
     // Import the `query` function from the `contract` module and the `QueryMsg` enum variants from the `msg` module
     use contract::query;
     use msg::QueryMsg::*;
 
     // Match the input `msg` argument against the `QueryMsg` enum variants
     match msg {
-        // If the input message is `Value`, call the `query::value(deps)?` function and serialize the result to a `Binary` value using the `to_binary` function
-        Value {} => to_binary(&query::value(deps)?),
+        // If the input message is `Value`, call the `query::value(deps, env)?` function and serialize the result to a `Binary` value using the `to_binary` function
+        Value {} => to_binary(&query::value(deps, env)?),
+        Vanity {} => to_binary(&query::vanity(deps)?),
+        IsAllowedDonor { addr } => to_binary(&query::is_allowed_donor(deps, addr)?),
+        IsBlocked { addr } => to_binary(&query::is_blocked(deps, addr)?),
+        RecentDonations { limit } => to_binary(&query::recent_donations(deps, limit)?),
+        Cw20Policy {} => to_binary(&query::cw20_policy(deps)?),
+        LastModified {} => to_binary(&query::last_modified(deps)?),
+        Streak { addr } => to_binary(&query::streak(deps, addr)?),
+        HasParent {} => to_binary(&query::has_parent(deps)?),
+        Role {} => to_binary(&query::role(deps)?),
+        ParentPeriod {} => to_binary(&query::parent_period(deps)?),
+        WouldForwardToParent {} => to_binary(&query::would_forward_to_parent(deps)?),
+        InitParams {} => to_binary(&query::init_params(deps)?),
+        WithdrawDestination {} => to_binary(&query::withdraw_destination(deps)?),
+        UntilNextMilestone {} => to_binary(&query::until_next_milestone(deps, env)?),
+        NextForward {} => to_binary(&query::next_forward(deps, env)?),
+        MaxDonation { denom } => to_binary(&query::max_donation(deps, denom)?),
+        Children { start_after, limit } => to_binary(&query::children(deps, start_after, limit)?),
+        TotalDonatedPaged { start_after, limit } => {
+            to_binary(&query::total_donated_paged(deps, start_after, limit)?)
+        }
+        TotalWithdrawn {} => to_binary(&query::total_withdrawn(deps)?),
+        Accounting {} => to_binary(&query::accounting(deps, env)?),
+        ReceiptCount {} => to_binary(&query::receipt_count(deps)?),
+        ProjectedAtHeight { height } => to_binary(&query::projected_at_height(deps, env, height)?),
+        CounterAtHeight { height } => to_binary(&query::counter_at_height(deps, height)?),
+        GoalProgress {} => to_binary(&query::goal_progress(deps, env)?),
+        TopDonors { limit } => to_binary(&query::top_donors(deps, limit)?),
+        TopDonorsByValue { limit } => to_binary(&query::top_donors_by_value(deps, limit)?),
+        IntegrityCheck {} => to_binary(&query::integrity_check(deps)?),
+        SchemaVersion {} => to_binary(&query::schema_version(deps)?),
+        MigrationKind {} => to_binary(&query::migration_kind(deps)?),
+        NeedsMigration {} => to_binary(&query::needs_migration(deps)?),
+        RoundsCompleted {} => to_binary(&query::rounds_completed(deps)?),
+        EffectivePolicy {} => to_binary(&query::effective_policy(deps, env)?),
+        Flags {} => to_binary(&query::flags(deps)?),
+        #[cfg(feature = "debug")]
+        Debug {} => to_binary(&query::debug(deps)?),
+        Withdrawable {} => to_binary(&query::withdrawable(deps, env)?),
+        AllowedActions { addr } => to_binary(&query::allowed_actions(deps, env, addr)?),
+        StateSize {} => to_binary(&query::state_size(deps)?),
+        Describe {} => to_binary(&query::describe(deps)?),
+        PreviewMigratedState { parent } => to_binary(&query::preview_migrated_state(deps, parent)?),
+        Predecessor {} => to_binary(&query::predecessor(deps)?),
     }
 }
 
@@ -53,15 +99,87 @@ pub fn execute(
     use contract::exec;
     use msg::ExecMsg::*;
 
-    match msg {
-        Donate {} => exec::donate(deps, env, info).map_err(ContractError::Std),
-        Reset { counter } => exec::reset(deps, info, counter),
+    let event_prefix = contract::event_prefix(deps.as_ref())?;
+    contract::record_modified(deps.storage, &env)?;
+
+    // Responses indexers correlate across upgrades carry the version of the code that produced them
+    let contract_version = matches!(
+        msg,
+        Donate { .. } | Reset { .. } | Withdraw {} | WithdrawTo { .. }
+    )
+    .then(|| get_contract_version(deps.storage))
+    .transpose()?;
+
+    let mut resp = match msg {
+        Donate {
+            forward_depth,
+            idempotency_key,
+        } => exec::donate(deps, env, info, forward_depth, idempotency_key),
+        Receive(msg) => exec::receive(deps, env, info, msg),
+        DonateMatching { reference_donor } => {
+            exec::donate_matching(deps, env, info, reference_donor)
+        }
+        Reset { counter } => exec::reset(deps, env, info, counter),
         Withdraw {} => exec::withdraw(deps, env, info),
+        SetWithdrawDestination { addr } => exec::set_withdraw_destination(deps, info, addr),
+        WithdrawAs { denom } => exec::withdraw_as(deps, env, info, denom),
         WithdrawTo { receiver, funds } => exec::withdraw_to(deps, env, info, receiver, funds),
+        WithdrawToMany { payouts } => exec::withdraw_to_many(deps, env, info, payouts),
+        AddAllowedDonor { addr } => exec::add_allowed_donor(deps, info, addr),
+        RemoveAllowedDonor { addr } => exec::remove_allowed_donor(deps, info, addr),
+        BlockDonor { addr } => exec::block_donor(deps, info, addr),
+        UnblockDonor { addr } => exec::unblock_donor(deps, info, addr),
+        SetParentPeriod { period } => exec::set_parent_period(deps, info, period),
+        RemoveParent {} => exec::remove_parent(deps, info),
+        ResetParentCountdown {} => exec::reset_parent_countdown(deps, info),
+        RegisterChild {} => exec::register_child(deps, env, info),
+        SetMinimalDonation {
+            minimal_donation,
+            grace_period,
+        } => exec::set_minimal_donation(deps, env, info, minimal_donation, grace_period),
+        Evacuate { to, donate } => exec::evacuate(deps, env, info, to, donate),
+        SweepCw20 { token, to } => exec::sweep_cw20(deps, env, info, token, to),
+        SetAcceptFundsWhenFree { accept } => exec::set_accept_funds_when_free(deps, info, accept),
+        UpdateConfig {
+            minimal_donation,
+            paused,
+            max_counter,
+        } => exec::update_config(deps, env, info, minimal_donation, paused, max_counter),
+        Fund {} => exec::fund(info),
+        Reconcile {} => exec::reconcile(deps, env, info),
+        IncrementVanity {} => exec::increment_vanity(deps, info),
+        ClaimStipend {} => exec::claim_stipend(deps, env, info),
+        TipLastDonor { amount } => exec::tip_last_donor(deps, env, info, amount),
+        FinalizeCampaign {} => exec::finalize_campaign(deps, env, info),
+        ScheduleDonation {
+            every_blocks,
+            amount,
+        } => exec::schedule_donation(deps, env, info, every_blocks, amount),
+        ExecuteScheduled { donor } => exec::execute_scheduled(deps, env, info, donor),
+        CancelScheduledDonation {} => exec::cancel_scheduled_donation(deps, info),
+        SetRefundable { refundable } => exec::set_refundable(deps, info, refundable),
+        RequestRefund {} => exec::request_refund(deps, env, info),
+        Handover { successor } => exec::handover(deps, env, info, successor),
+        SetPredecessor { addr } => exec::set_predecessor(deps, info, addr),
+        ImportCounter { counter } => exec::import_counter(deps, env, info, counter),
+        RecomputeStats {} => exec::recompute_stats(deps, env, info),
+    }?;
+
+    if let Some(contract_version) = contract_version {
+        resp = resp.add_attribute("contract_version", contract_version.version);
     }
+
+    Ok(contract::prefix_attributes(resp, event_prefix))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(deps: DepsMut, _env: Env, msg: msg::MigrateMsg) -> Result<Response, ContractError> {
+pub fn migrate(deps: DepsMut, env: Env, msg: msg::MigrateMsg) -> Result<Response, ContractError> {
+    contract::record_modified(deps.storage, &env)?;
     contract::migrate(deps, msg.parent)
 }
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, env: Env, reply: Reply) -> Result<Response, ContractError> {
+    contract::record_modified(deps.storage, &env)?;
+    contract::reply(deps, reply)
+}
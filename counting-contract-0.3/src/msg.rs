@@ -1,5 +1,5 @@
-use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::{Coin, Decimal};
+use cosmwasm_schema::{cw_serde, generate_api, Api, QueryResponses};
+use cosmwasm_std::{Addr, Binary, Coin, Decimal, Uint128};
 
 #[cw_serde]
 #[derive(QueryResponses)]
@@ -7,12 +7,191 @@ pub enum QueryMsg {
     // Define a variant called Value that takes no parameters.
     #[returns(ValueResp)]
     Value {},
+
+    // Define a variant called Vanity which returns the vanity counter, independent from donations.
+    #[returns(ValueResp)]
+    Vanity {},
+
+    #[returns(bool)]
+    IsAllowedDonor { addr: String },
+
+    #[returns(bool)]
+    IsBlocked { addr: String },
+
+    #[returns(bool)]
+    HasParent {},
+
+    // Define a variant called Role which tells if the contract is the root of a donation tree or a child of some parent.
+    #[returns(RoleResp)]
+    Role {},
+
+    // Define a variant called Children which lists the child contracts registered with this one, a page at a time.
+    #[returns(Vec<Addr>)]
+    Children {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    // Define a variant called ParentPeriod which returns the configured donating period, not the live countdown.
+    #[returns(Option<u64>)]
+    ParentPeriod {},
+
+    // Define a variant called WouldForwardToParent which tells if the next qualifying donation is forwarded to the parent.
+    #[returns(bool)]
+    WouldForwardToParent {},
+
+    // Define a variant called InitParams which returns the message the contract was instantiated with.
+    #[returns(Option<InstantiateMsg>)]
+    InitParams {},
+
+    // Define a variant called WithdrawDestination which returns where `Withdraw` sends the funds.
+    #[returns(Addr)]
+    WithdrawDestination {},
+
+    // Define a variant called UntilNextMilestone which returns how many counted donations the next milestone is away.
+    #[returns(Option<u64>)]
+    UntilNextMilestone {},
+
+    // Define a variant called NextForward which previews when the parent is donated next and roughly how much.
+    #[returns(Option<NextForwardResp>)]
+    NextForward {},
+
+    #[returns(Coin)]
+    MaxDonation { denom: String },
+
+    // Define a variant called TotalDonatedPaged which lists the total donated per denom, a page at a time.
+    #[returns(Vec<Coin>)]
+    TotalDonatedPaged {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    // Define a variant called Streak which returns how many donations in a row the donor made within the streak window.
+    #[returns(u64)]
+    Streak { addr: String },
+
+    // Define a variant called LastModified which returns the height of the last state change.
+    #[returns(u64)]
+    LastModified {},
+
+    // Define a variant called Cw20Policy which returns the cw20 token counted as donations and its minimum.
+    #[returns(Option<Cw20PolicyResp>)]
+    Cw20Policy {},
+
+    // Define a variant called RecentDonations which lists the latest donations, newest first.
+    #[returns(Vec<DonationRecord>)]
+    RecentDonations { limit: Option<u32> },
+
+    // Define a variant called TotalWithdrawn which lists everything withdrawn so far, per denom.
+    #[returns(Vec<Coin>)]
+    TotalWithdrawn {},
+
+    // Define a variant called Accounting which puts the donated, withdrawn and held funds side by side.
+    #[returns(AccountingResp)]
+    Accounting {},
+
+    // Define a variant called ReceiptCount which returns how many donation receipts were issued.
+    #[returns(u64)]
+    ReceiptCount {},
+
+    // Define a variant called ProjectedAtHeight which returns the value the counter decays to by a future height.
+    #[returns(ValueResp)]
+    ProjectedAtHeight { height: u64 },
+
+    // Define a variant called CounterAtHeight which returns the counter as it was at the given block height.
+    #[returns(ValueResp)]
+    CounterAtHeight { height: u64 },
+
+    // Define a variant called TopDonors which ranks donors by the number of donations counted.
+    // Ties are broken by address, in lexicographic order.
+    #[returns(TopDonorsResp)]
+    TopDonors { limit: Option<u32> },
+
+    // Define a variant called TopDonorsByValue which ranks donors by the total donated in the minimal donation denom.
+    // Ties are broken by address, in lexicographic order.
+    #[returns(TopDonorsByValueResp)]
+    TopDonorsByValue { limit: Option<u32> },
+
+    // Define a variant called IntegrityCheck which tells the stored contract version and if the state still loads,
+    // answering even for a half-migrated contract.
+    #[returns(IntegrityCheckResp)]
+    IntegrityCheck {},
+
+    // Define a variant called SchemaVersion which breaks the stored contract version down into its parts.
+    #[returns(SchemaVersionResp)]
+    SchemaVersion {},
+
+    // Define a variant called MigrationKind which tells which migration steps the contract still needs.
+    #[returns(MigrationKind)]
+    MigrationKind {},
+
+    // Define a variant called NeedsMigration which tells if the stored contract version is not the current one.
+    #[returns(bool)]
+    NeedsMigration {},
+
+    // Define a variant called GoalProgress which shows how close the counter is to `max_counter`.
+    #[returns(GoalProgressResp)]
+    GoalProgress {},
+
+    // Define a variant called RoundsCompleted which returns how many times the counter went through `round_size`.
+    #[returns(u64)]
+    RoundsCompleted {},
+
+    // Define a variant called EffectivePolicy which lists every donation currently counted,
+    // including the denoms still grandfathered at the current height.
+    #[returns(EffectivePolicyResp)]
+    EffectivePolicy {},
+
+    // Define a variant called Flags which tells which optional behaviours are enabled.
+    #[returns(FlagsResp)]
+    Flags {},
+
+    // Define a variant called Debug which dumps the raw storage, only built with the `debug` feature.
+    #[cfg(feature = "debug")]
+    #[returns(DebugResp)]
+    Debug {},
+
+    // Define a variant called Withdrawable which returns what the owner would get from a withdraw right now.
+    #[returns(Vec<Coin>)]
+    Withdrawable {},
+
+    // Define a variant called AllowedActions which lists the messages `addr` may execute right now, by action name.
+    #[returns(Vec<String>)]
+    AllowedActions { addr: String },
+
+    // Define a variant called StateSize which returns the size of the serialized `State` in bytes.
+    #[returns(u64)]
+    StateSize {},
+
+    // Define a variant called Describe which sums up the configuration in a single readable line.
+    #[returns(String)]
+    Describe {},
+
+    // Define a variant called PreviewMigratedState which shows the state `migrate` would produce.
+    #[returns(MigratedStateResp)]
+    PreviewMigratedState { parent: Option<Parent> },
+
+    // Define a variant called Predecessor which returns the contract allowed to hand over to this one, if any.
+    #[returns(Option<Addr>)]
+    Predecessor {},
 }
 
 #[cw_serde]
 pub enum ExecMsg {
-    // Define a variant called Donate that takes no parameters.
-    Donate {},
+    // Define a variant called Donate which takes how many contracts the donation was already forwarded through,
+    // and an optional key so a relayer submitting the same donation twice gets it counted only once.
+    Donate {
+        forward_depth: Option<u8>,
+        idempotency_key: Option<String>,
+    },
+
+    // Define a variant called Receive which cw20 tokens call when they are sent to this contract.
+    Receive(Cw20ReceiveMsg),
+
+    // Define a variant called DonateMatching which donates at least as much as `reference_donor` did last time.
+    DonateMatching {
+        reference_donor: String,
+    },
 
     // Define a variant called Reset that takes a single parameter called counter which defaults to 0.
     Reset {
@@ -23,11 +202,158 @@ pub enum ExecMsg {
     // Define a variant called Withdraw that takes no parameters.
     Withdraw {},
 
+    // Define a variant called SetWithdrawDestination which makes `Withdraw` pay to `addr` instead of the owner.
+    SetWithdrawDestination {
+        addr: String,
+    },
+
+    // Define a variant called WithdrawAs which withdraws like Withdraw, but only when every coin is in `denom`.
+    WithdrawAs {
+        denom: String,
+    },
+
+    // Define a variant called WithdrawTo which sends at most `funds` to `receiver`, or the whole balance when `funds` is empty.
     WithdrawTo {
         receiver: String,
         #[serde(default)]
         funds: Vec<Coin>,
     },
+
+    // Define a variant called WithdrawToMany which pays out to several receivers at once.
+    WithdrawToMany {
+        payouts: Vec<Payout>,
+    },
+
+    AddAllowedDonor {
+        addr: String,
+    },
+
+    RemoveAllowedDonor {
+        addr: String,
+    },
+
+    BlockDonor {
+        addr: String,
+    },
+
+    UnblockDonor {
+        addr: String,
+    },
+
+    // Define a variant called SetParentPeriod which changes how often the parent gets donated and restarts the countdown.
+    SetParentPeriod {
+        period: u64,
+    },
+
+    // Define a variant called RemoveParent which stops donating to the parent.
+    RemoveParent {},
+
+    // Define a variant called ResetParentCountdown which restarts the countdown from the configured period.
+    ResetParentCountdown {},
+
+    // Define a variant called RegisterChild which a child contract sends to its parent to be listed in `Children`.
+    RegisterChild {},
+
+    // Define a variant called SetMinimalDonation which changes the minimal donation.
+    // When the denom changes, the previous one still counts for `grace_period` blocks.
+    SetMinimalDonation {
+        minimal_donation: Coin,
+        grace_period: Option<u64>,
+    },
+
+    // Define a variant called Evacuate which moves the whole balance to a new deployment, donating it there when `donate` is set.
+    Evacuate {
+        to: String,
+        #[serde(default)]
+        donate: bool,
+    },
+
+    // Define a variant called SweepCw20 which sends the whole balance of a cw20 token held by this contract to `to`.
+    SweepCw20 {
+        token: String,
+        to: String,
+    },
+
+    // Define a variant called SetAcceptFundsWhenFree which tells if a free contract keeps the funds donated to it.
+    SetAcceptFundsWhenFree {
+        accept: bool,
+    },
+
+    // Define a variant called UpdateConfig which applies every provided setting at once, leaving the others untouched.
+    UpdateConfig {
+        minimal_donation: Option<Coin>,
+        paused: Option<bool>,
+        max_counter: Option<u64>,
+    },
+
+    // Define a variant called Fund which keeps the sent funds without counting them as a donation.
+    Fund {},
+
+    // Define a variant called ScheduleDonation which donates `amount` every `every_blocks` blocks,
+    // out of the funds sent along as a deposit.
+    ScheduleDonation {
+        every_blocks: u64,
+        amount: Coin,
+    },
+
+    // Define a variant called ExecuteScheduled which anyone can call to apply the donor's scheduled donation once due.
+    ExecuteScheduled {
+        donor: String,
+    },
+
+    // Define a variant called CancelScheduledDonation which stops the sender's scheduled donation and returns what is left of its deposit.
+    CancelScheduledDonation {},
+
+    // Define a variant called Reconcile which counts the funds sent to the contract outside of Donate.
+    Reconcile {},
+
+    // Define a variant called IncrementVanity which bumps the vanity counter by one.
+    IncrementVanity {},
+
+    // Define a variant called ClaimStipend which sends the configured stipend to the owner.
+    ClaimStipend {},
+
+    // Define a variant called TipLastDonor which sends `amount` to whoever donated last.
+    TipLastDonor {
+        amount: Coin,
+    },
+
+    // Define a variant called FinalizeCampaign which anyone can call once the goal is reached,
+    // sweeping the funds to the owner and pausing donations.
+    FinalizeCampaign {},
+
+    // Define a variant called SetRefundable which lets donors take their donations back, for cancelled campaigns.
+    SetRefundable {
+        refundable: bool,
+    },
+
+    // Define a variant called RequestRefund which sends the sender's own donations back and uncounts them.
+    RequestRefund {},
+
+    // Define a variant called Handover which moves the funds and the counter to `successor`, then pauses this contract.
+    // Donor stats, totals and the rest of the configuration are not carried over, they stay queryable here.
+    Handover {
+        successor: String,
+    },
+
+    // Define a variant called SetPredecessor which lets `addr` hand its counter over to this contract, once.
+    SetPredecessor {
+        addr: String,
+    },
+
+    // Define a variant called ImportCounter which the predecessor sends on `Handover` to carry its counter over.
+    ImportCounter {
+        counter: u64,
+    },
+
+    // Define a variant called RecomputeStats which checks the per-donor counts against the counter.
+    RecomputeStats {},
+}
+
+#[cw_serde]
+pub struct Payout {
+    pub receiver: String,
+    pub amount: Coin,
 }
 
 #[cw_serde]
@@ -35,9 +361,13 @@ pub struct Parent {
     pub addr: String,
     pub donating_period: u64,
     pub part: Decimal,
+
+    // Define a field called fee which is kept by this contract out of every parent donation.
+    pub fee: Option<Coin>,
 }
 
 #[cw_serde]
+#[derive(Default)]
 pub struct InstantiateMsg {
     // Define a field called counter of type u64 which defaults to 0.
     #[serde(default)]
@@ -47,6 +377,87 @@ pub struct InstantiateMsg {
     pub minimal_donation: Coin,
 
     pub parent: Option<Parent>,
+
+    // Further parents, each getting its own `part` every `donating_period` qualifying donations.
+    #[serde(default)]
+    pub extra_parents: Vec<Parent>,
+
+    // When set, only the listed addresses are allowed to donate.
+    pub allowed_donors: Option<Vec<String>>,
+
+    // When set, every emitted attribute key is prefixed with it, e.g. `counting/action`.
+    pub event_prefix: Option<String>,
+
+    // When set, donations made after the counter reaches it are refunded.
+    pub max_counter: Option<u64>,
+
+    // When set, anything above the minimal donation in its denom is sent back to the donor.
+    #[serde(default)]
+    pub refund_excess: bool,
+
+    // When set, the counter goes back to zero every time it reaches a multiple of it.
+    pub round_size: Option<u64>,
+
+    // When set, the beneficiary receives this fraction of every withdraw, the owner gets the rest.
+    pub withdraw_cut: Option<(String, Decimal)>,
+
+    // When set, withdraws are at least this many seconds apart.
+    pub withdraw_cooldown: Option<u64>,
+
+    // When set, withdraws always leave at least this much in the contract.
+    pub reserve: Option<Coin>,
+
+    // When set, the owner can claim `stipend` once every `stipend_period` blocks.
+    pub stipend: Option<Coin>,
+    #[serde(default)]
+    pub stipend_period: u64,
+
+    // When set, the counter loses this much for every block without a donation, down to zero.
+    pub decay_per_block: Option<u64>,
+
+    // When set, donations forwarded through this many contracts are not forwarded any further.
+    pub max_forward_depth: Option<u8>,
+
+    // When set, donations at most this many blocks apart extend the donor's streak.
+    pub streak_window: Option<u64>,
+
+    // When set, `Send`s of at least this amount from the given cw20 token count as donations.
+    pub cw20_minimal_donation: Option<(String, Uint128)>,
+
+    // When set, donations short of the minimal amount by at most this much still count.
+    pub tolerance: Option<Uint128>,
+
+    // When set, the donation bringing the counter to a multiple of it is marked with a `milestone` attribute.
+    pub milestone_size: Option<u64>,
+
+    // When set, the minimal donation amount is read as basis points of the donor's balance in its denom,
+    // taken before the donation.
+    #[serde(default)]
+    pub minimal_donation_bps: bool,
+}
+
+// Same shape as `cw20::Cw20ReceiveMsg`, the hook cw20 tokens call on `Send`
+#[cw_serde]
+pub struct Cw20ReceiveMsg {
+    pub sender: String,
+    pub amount: Uint128,
+    pub msg: Binary,
+}
+
+// Only the parts of the cw20 interface this contract calls on tokens
+#[cw_serde]
+pub enum Cw20ExecuteMsg {
+    Transfer { recipient: String, amount: Uint128 },
+}
+
+#[cw_serde]
+pub enum Cw20QueryMsg {
+    Balance { address: String },
+}
+
+#[cw_serde]
+pub struct Cw20BalanceResponse {
+    pub balance: Uint128,
 }
 
 #[cw_serde]
@@ -59,3 +470,157 @@ pub struct ValueResp {
     // Define a field called value of type u64.
     pub value: u64,
 }
+
+#[cw_serde]
+pub struct MigratedStateResp {
+    pub counter: u64,
+    pub minimal_donation: Coin,
+    pub owner: Addr,
+    pub donating_parent: Option<u64>,
+}
+
+#[cw_serde]
+pub enum RoleResp {
+    Root,
+    Child { parent: Addr },
+}
+
+#[cw_serde]
+pub struct NextForwardResp {
+    pub periods_remaining: u64,
+    pub estimated_amount: Vec<Coin>,
+}
+
+#[cw_serde]
+pub struct AccountingResp {
+    pub donated: Vec<Coin>,
+    pub withdrawn: Vec<Coin>,
+    pub held: Vec<Coin>,
+}
+
+#[cw_serde]
+pub struct GoalProgressResp {
+    pub current: u64,
+    pub goal: u64,
+    pub fraction: Decimal,
+}
+
+#[cw_serde]
+pub struct IntegrityCheckResp {
+    pub cw2_version: String,
+    pub state_loadable: bool,
+}
+
+#[cw_serde]
+pub struct SchemaVersionResp {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+}
+
+#[cw_serde]
+pub struct DonorCount {
+    pub addr: Addr,
+    pub count: u64,
+}
+
+#[cw_serde]
+pub struct TopDonorsResp {
+    pub donors: Vec<DonorCount>,
+}
+
+#[cw_serde]
+pub struct DonorTotal {
+    pub addr: Addr,
+    pub total: Uint128,
+}
+
+#[cw_serde]
+pub struct TopDonorsByValueResp {
+    pub donors: Vec<DonorTotal>,
+}
+
+#[cw_serde]
+pub struct EffectivePolicyResp {
+    pub accepted: Vec<Coin>,
+}
+
+#[cw_serde]
+pub struct FlagsResp {
+    pub paused: bool,
+    pub refund_excess: bool,
+    pub accept_funds_when_free: bool,
+    pub allowed_donors_only: bool,
+    pub has_parent: bool,
+    pub has_goal: bool,
+    pub has_rounds: bool,
+    pub has_withdraw_cut: bool,
+    pub has_decay: bool,
+    pub minimal_donation_bps: bool,
+    pub has_tolerance: bool,
+    pub has_reserve: bool,
+    pub has_withdraw_cooldown: bool,
+    pub has_stipend: bool,
+    pub refundable: bool,
+    pub has_milestones: bool,
+    pub has_streaks: bool,
+    pub has_cw20_policy: bool,
+    pub has_max_forward_depth: bool,
+    pub has_event_prefix: bool,
+}
+
+#[cw_serde]
+pub struct StorageEntry {
+    pub key: String,
+    pub value: String,
+}
+
+#[cw_serde]
+pub struct DebugResp {
+    pub entries: Vec<StorageEntry>,
+}
+
+#[cw_serde]
+pub enum MigrationKind {
+    FromV1,
+    FromV2,
+    FromV3,
+    Current,
+    Unknown,
+}
+
+// cw20 donations are recorded with the token address as the denom
+#[cw_serde]
+pub struct DonationRecord {
+    pub donor: Addr,
+    pub amount: Vec<Coin>,
+    pub height: u64,
+    pub counted: bool,
+}
+
+#[cw_serde]
+pub struct Cw20PolicyResp {
+    pub token: Addr,
+    pub minimal_donation: Uint128,
+}
+
+#[cw_serde]
+pub struct MigrateResp {
+    pub from: String,
+    pub to: String,
+}
+
+#[cw_serde]
+pub struct WithdrawResp {
+    pub sent: Vec<Coin>,
+}
+
+// The messages `bin/schema.rs` writes out, kept here so the tests check the very same list
+pub fn api() -> Api {
+    generate_api! {
+        instantiate: InstantiateMsg,
+        execute: ExecMsg,
+        query: QueryMsg,
+        migrate: MigrateMsg,
+    }
+}
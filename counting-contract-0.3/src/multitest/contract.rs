@@ -1,13 +1,19 @@
-use cosmwasm_std::{Addr, Coin, StdResult};
+use cosmwasm_std::{from_binary, Addr, Binary, Coin, Decimal, StdResult};
 use cw_multi_test::{App, ContractWrapper, Executor};
 
 use crate::{
     error::ContractError,
     execute, instantiate, migrate,
-    msg::{ExecMsg, InstantiateMsg, MigrateMsg, Parent, QueryMsg, ValueResp},
-    query,
+    msg::{
+        AccountingResp, Cw20PolicyResp, Cw20ReceiveMsg, DonationRecord, EffectivePolicyResp,
+        ExecMsg, FlagsResp, GoalProgressResp, InstantiateMsg, IntegrityCheckResp, MigrateMsg,
+        MigrateResp, MigratedStateResp, MigrationKind, NextForwardResp, Parent, Payout, QueryMsg,
+        RoleResp, SchemaVersionResp, TopDonorsByValueResp, TopDonorsResp, ValueResp, WithdrawResp,
+    },
+    query, reply,
 };
 
+#[derive(Debug)]
 pub struct CountingContract(Addr);
 
 impl CountingContract {
@@ -16,7 +22,9 @@ impl CountingContract {
     }
 
     pub fn store_code(app: &mut App) -> u64 {
-        let contract = ContractWrapper::new(execute, instantiate, query).with_migrate(migrate);
+        let contract = ContractWrapper::new(execute, instantiate, query)
+            .with_migrate(migrate)
+            .with_reply(reply);
         app.store_code(Box::new(contract))
     }
 
@@ -41,6 +49,27 @@ impl CountingContract {
     }
 
     #[track_caller]
+    pub fn migrate_with_resp(
+        app: &mut App,
+        contract: Addr,
+        code_id: u64,
+        sender: &Addr,
+        parent: impl Into<Option<Parent>>,
+    ) -> Result<(Self, MigrateResp), ContractError> {
+        let parent = parent.into();
+
+        app.migrate_contract(
+            sender.clone(),
+            contract.clone(),
+            &MigrateMsg { parent },
+            code_id,
+        )
+        .map_err(|err| err.downcast().unwrap())
+        .map(|resp| (Self(contract), from_binary(&resp.data.unwrap()).unwrap()))
+    }
+
+    #[track_caller]
+    #[allow(clippy::too_many_arguments)]
     pub fn instantiate<'a>(
         app: &mut App,
         code_id: u64,
@@ -50,19 +79,77 @@ impl CountingContract {
         counter: impl Into<Option<u64>>,
         minimal_donation: Coin,
         parent: impl Into<Option<Parent>>,
-    ) -> StdResult<Self> {
-        let admin = admin.into();
+    ) -> Result<Self, ContractError> {
         let counter: u64 = counter.into().unwrap_or_default();
         let parent = parent.into();
 
-        app.instantiate_contract(
+        Self::instantiate_with_msg(
+            app,
             code_id,
-            sender.clone(),
+            sender,
+            label,
+            admin,
             &InstantiateMsg {
                 counter,
                 minimal_donation,
                 parent,
+                ..Default::default()
             },
+        )
+    }
+
+    // Each contract donates to the one instantiated before it, the root comes first and the leaf last
+    #[track_caller]
+    pub fn instantiate_chain(
+        app: &mut App,
+        code_id: u64,
+        sender: &Addr,
+        len: usize,
+        minimal_donation: Coin,
+        donating_period: u64,
+        part: Decimal,
+    ) -> Result<Vec<Self>, ContractError> {
+        let mut chain: Vec<Self> = Vec::with_capacity(len);
+
+        for n in 0..len {
+            let parent = chain.last().map(|parent| Parent {
+                addr: parent.addr().to_string(),
+                donating_period,
+                part,
+                fee: None,
+            });
+
+            let contract = Self::instantiate(
+                app,
+                code_id,
+                sender,
+                &format!("Counting contract {n}"),
+                None,
+                None,
+                minimal_donation.clone(),
+                parent,
+            )?;
+            chain.push(contract);
+        }
+
+        Ok(chain)
+    }
+
+    #[track_caller]
+    pub fn instantiate_with_msg<'a>(
+        app: &mut App,
+        code_id: u64,
+        sender: &Addr,
+        label: &str,
+        admin: impl Into<Option<&'a Addr>>,
+        msg: &InstantiateMsg,
+    ) -> Result<Self, ContractError> {
+        let admin = admin.into();
+
+        app.instantiate_contract(
+            code_id,
+            sender.clone(),
+            msg,
             &[],
             label,
             admin.map(Addr::to_string),
@@ -71,6 +158,11 @@ impl CountingContract {
         .map_err(|err| err.downcast().unwrap())
     }
 
+    // Moves the chain forward by `secs`, for the time based limits
+    pub fn advance_seconds(&self, app: &mut App, secs: u64) {
+        app.update_block(|block| block.time = block.time.plus_seconds(secs));
+    }
+
     #[track_caller]
     pub fn donate(
         &self,
@@ -81,7 +173,51 @@ impl CountingContract {
         app.execute_contract(
             sender.clone(),
             self.addr().clone(),
-            &ExecMsg::Donate {},
+            &ExecMsg::Donate {
+                forward_depth: None,
+                idempotency_key: None,
+            },
+            funds,
+        )
+        .map_err(|err| err.downcast().unwrap())
+        .map(|_| ())
+    }
+
+    #[track_caller]
+    pub fn donate_with_key(
+        &self,
+        app: &mut App,
+        sender: &Addr,
+        funds: &[Coin],
+        idempotency_key: &str,
+    ) -> Result<(), ContractError> {
+        app.execute_contract(
+            sender.clone(),
+            self.addr().clone(),
+            &ExecMsg::Donate {
+                forward_depth: None,
+                idempotency_key: Some(idempotency_key.to_owned()),
+            },
+            funds,
+        )
+        .map_err(|err| err.downcast().unwrap())
+        .map(|_| ())
+    }
+
+    #[track_caller]
+    pub fn donate_matching(
+        &self,
+        app: &mut App,
+        sender: &Addr,
+        reference_donor: &Addr,
+        funds: &[Coin],
+    ) -> Result<(), ContractError> {
+        app.execute_contract(
+            sender.clone(),
+            self.addr().clone(),
+            &ExecMsg::DonateMatching {
+                reference_donor: reference_donor.to_string(),
+            },
             funds,
         )
         .map_err(|err| err.downcast().unwrap())
@@ -107,7 +243,7 @@ impl CountingContract {
     }
 
     #[track_caller]
-    pub fn withdraw(&self, app: &mut App, sender: &Addr) -> Result<(), ContractError> {
+    pub fn withdraw(&self, app: &mut App, sender: &Addr) -> Result<WithdrawResp, ContractError> {
         app.execute_contract(
             sender.clone(),
             self.addr().clone(),
@@ -115,6 +251,82 @@ impl CountingContract {
             &[],
         )
         .map_err(|err| err.downcast().unwrap())
+        .map(|resp| from_binary(&resp.data.unwrap()).unwrap())
+    }
+
+    #[track_caller]
+    pub fn withdraw_as(
+        &self,
+        app: &mut App,
+        sender: &Addr,
+        denom: &str,
+    ) -> Result<WithdrawResp, ContractError> {
+        app.execute_contract(
+            sender.clone(),
+            self.addr().clone(),
+            &ExecMsg::WithdrawAs {
+                denom: denom.to_owned(),
+            },
+            &[],
+        )
+        .map_err(|err| err.downcast().unwrap())
+        .map(|resp| from_binary(&resp.data.unwrap()).unwrap())
+    }
+
+    #[track_caller]
+    pub fn set_withdraw_destination(
+        &self,
+        app: &mut App,
+        sender: &Addr,
+        addr: &Addr,
+    ) -> Result<(), ContractError> {
+        app.execute_contract(
+            sender.clone(),
+            self.addr().clone(),
+            &ExecMsg::SetWithdrawDestination {
+                addr: addr.to_string(),
+            },
+            &[],
+        )
+        .map_err(|err| err.downcast().unwrap())
+        .map(|_| ())
+    }
+
+    #[track_caller]
+    pub fn handover(
+        &self,
+        app: &mut App,
+        sender: &Addr,
+        successor: &Addr,
+    ) -> Result<(), ContractError> {
+        app.execute_contract(
+            sender.clone(),
+            self.addr().clone(),
+            &ExecMsg::Handover {
+                successor: successor.to_string(),
+            },
+            &[],
+        )
+        .map_err(|err| err.downcast().unwrap())
+        .map(|_| ())
+    }
+
+    #[track_caller]
+    pub fn set_predecessor(
+        &self,
+        app: &mut App,
+        sender: &Addr,
+        addr: &Addr,
+    ) -> Result<(), ContractError> {
+        app.execute_contract(
+            sender.clone(),
+            self.addr().clone(),
+            &ExecMsg::SetPredecessor {
+                addr: addr.to_string(),
+            },
+            &[],
+        )
+        .map_err(|err| err.downcast().unwrap())
         .map(|_| ())
     }
 
@@ -125,7 +337,7 @@ impl CountingContract {
         sender: &Addr,
         receiver: &Addr,
         funds: impl Into<Option<Vec<Coin>>>,
-    ) -> Result<(), ContractError> {
+    ) -> Result<WithdrawResp, ContractError> {
         let funds: Vec<Coin> = funds.into().unwrap_or_default();
         app.execute_contract(
             sender.clone(),
@@ -137,13 +349,755 @@ impl CountingContract {
             &[],
         )
         .map_err(|err| err.downcast().unwrap())
+        .map(|resp| from_binary(&resp.data.unwrap()).unwrap())
+    }
+
+    #[track_caller]
+    pub fn withdraw_to_many(
+        &self,
+        app: &mut App,
+        sender: &Addr,
+        payouts: Vec<Payout>,
+    ) -> Result<(), ContractError> {
+        app.execute_contract(
+            sender.clone(),
+            self.addr().clone(),
+            &ExecMsg::WithdrawToMany { payouts },
+            &[],
+        )
+        .map_err(|err| err.downcast().unwrap())
         .map(|_| ())
     }
 
     #[track_caller]
-    pub fn query_value(&self, app: &App) -> StdResult<ValueResp> {
-        app.wrap()
-            .query_wasm_smart(self.addr().clone(), &QueryMsg::Value {})
+    pub fn add_allowed_donor(
+        &self,
+        app: &mut App,
+        sender: &Addr,
+        addr: &Addr,
+    ) -> Result<(), ContractError> {
+        app.execute_contract(
+            sender.clone(),
+            self.addr().clone(),
+            &ExecMsg::AddAllowedDonor {
+                addr: addr.to_string(),
+            },
+            &[],
+        )
+        .map_err(|err| err.downcast().unwrap())
+        .map(|_| ())
+    }
+
+    #[track_caller]
+    pub fn remove_allowed_donor(
+        &self,
+        app: &mut App,
+        sender: &Addr,
+        addr: &Addr,
+    ) -> Result<(), ContractError> {
+        app.execute_contract(
+            sender.clone(),
+            self.addr().clone(),
+            &ExecMsg::RemoveAllowedDonor {
+                addr: addr.to_string(),
+            },
+            &[],
+        )
+        .map_err(|err| err.downcast().unwrap())
+        .map(|_| ())
+    }
+
+    #[track_caller]
+    pub fn block_donor(
+        &self,
+        app: &mut App,
+        sender: &Addr,
+        addr: &Addr,
+    ) -> Result<(), ContractError> {
+        app.execute_contract(
+            sender.clone(),
+            self.addr().clone(),
+            &ExecMsg::BlockDonor {
+                addr: addr.to_string(),
+            },
+            &[],
+        )
+        .map_err(|err| err.downcast().unwrap())
+        .map(|_| ())
+    }
+
+    #[track_caller]
+    pub fn unblock_donor(
+        &self,
+        app: &mut App,
+        sender: &Addr,
+        addr: &Addr,
+    ) -> Result<(), ContractError> {
+        app.execute_contract(
+            sender.clone(),
+            self.addr().clone(),
+            &ExecMsg::UnblockDonor {
+                addr: addr.to_string(),
+            },
+            &[],
+        )
+        .map_err(|err| err.downcast().unwrap())
+        .map(|_| ())
+    }
+
+    #[track_caller]
+    pub fn set_parent_period(
+        &self,
+        app: &mut App,
+        sender: &Addr,
+        period: u64,
+    ) -> Result<(), ContractError> {
+        app.execute_contract(
+            sender.clone(),
+            self.addr().clone(),
+            &ExecMsg::SetParentPeriod { period },
+            &[],
+        )
+        .map_err(|err| err.downcast().unwrap())
+        .map(|_| ())
+    }
+
+    #[track_caller]
+    pub fn remove_parent(&self, app: &mut App, sender: &Addr) -> Result<(), ContractError> {
+        app.execute_contract(
+            sender.clone(),
+            self.addr().clone(),
+            &ExecMsg::RemoveParent {},
+            &[],
+        )
+        .map_err(|err| err.downcast().unwrap())
+        .map(|_| ())
+    }
+
+    #[track_caller]
+    pub fn reset_parent_countdown(
+        &self,
+        app: &mut App,
+        sender: &Addr,
+    ) -> Result<(), ContractError> {
+        app.execute_contract(
+            sender.clone(),
+            self.addr().clone(),
+            &ExecMsg::ResetParentCountdown {},
+            &[],
+        )
+        .map_err(|err| err.downcast().unwrap())
+        .map(|_| ())
+    }
+
+    #[track_caller]
+    pub fn set_minimal_donation(
+        &self,
+        app: &mut App,
+        sender: &Addr,
+        minimal_donation: Coin,
+        grace_period: impl Into<Option<u64>>,
+    ) -> Result<(), ContractError> {
+        let grace_period = grace_period.into();
+
+        app.execute_contract(
+            sender.clone(),
+            self.addr().clone(),
+            &ExecMsg::SetMinimalDonation {
+                minimal_donation,
+                grace_period,
+            },
+            &[],
+        )
+        .map_err(|err| err.downcast().unwrap())
+        .map(|_| ())
+    }
+
+    #[track_caller]
+    pub fn set_accept_funds_when_free(
+        &self,
+        app: &mut App,
+        sender: &Addr,
+        accept: bool,
+    ) -> Result<(), ContractError> {
+        app.execute_contract(
+            sender.clone(),
+            self.addr().clone(),
+            &ExecMsg::SetAcceptFundsWhenFree { accept },
+            &[],
+        )
+        .map_err(|err| err.downcast().unwrap())
+        .map(|_| ())
+    }
+
+    #[track_caller]
+    pub fn set_refundable(
+        &self,
+        app: &mut App,
+        sender: &Addr,
+        refundable: bool,
+    ) -> Result<(), ContractError> {
+        app.execute_contract(
+            sender.clone(),
+            self.addr().clone(),
+            &ExecMsg::SetRefundable { refundable },
+            &[],
+        )
+        .map_err(|err| err.downcast().unwrap())
+        .map(|_| ())
+    }
+
+    #[track_caller]
+    pub fn request_refund(&self, app: &mut App, sender: &Addr) -> Result<(), ContractError> {
+        app.execute_contract(
+            sender.clone(),
+            self.addr().clone(),
+            &ExecMsg::RequestRefund {},
+            &[],
+        )
+        .map_err(|err| err.downcast().unwrap())
+        .map(|_| ())
+    }
+
+    #[track_caller]
+    pub fn update_config(
+        &self,
+        app: &mut App,
+        sender: &Addr,
+        minimal_donation: impl Into<Option<Coin>>,
+        paused: impl Into<Option<bool>>,
+        max_counter: impl Into<Option<u64>>,
+    ) -> Result<(), ContractError> {
+        let minimal_donation = minimal_donation.into();
+        let paused = paused.into();
+        let max_counter = max_counter.into();
+
+        app.execute_contract(
+            sender.clone(),
+            self.addr().clone(),
+            &ExecMsg::UpdateConfig {
+                minimal_donation,
+                paused,
+                max_counter,
+            },
+            &[],
+        )
+        .map_err(|err| err.downcast().unwrap())
+        .map(|_| ())
+    }
+
+    #[track_caller]
+    pub fn fund(&self, app: &mut App, sender: &Addr, funds: &[Coin]) -> Result<(), ContractError> {
+        app.execute_contract(
+            sender.clone(),
+            self.addr().clone(),
+            &ExecMsg::Fund {},
+            funds,
+        )
+        .map_err(|err| err.downcast().unwrap())
+        .map(|_| ())
+    }
+
+    #[track_caller]
+    pub fn schedule_donation(
+        &self,
+        app: &mut App,
+        sender: &Addr,
+        every_blocks: u64,
+        amount: Coin,
+        deposit: &[Coin],
+    ) -> Result<(), ContractError> {
+        app.execute_contract(
+            sender.clone(),
+            self.addr().clone(),
+            &ExecMsg::ScheduleDonation {
+                every_blocks,
+                amount,
+            },
+            deposit,
+        )
+        .map_err(|err| err.downcast().unwrap())
+        .map(|_| ())
+    }
+
+    #[track_caller]
+    pub fn execute_scheduled(
+        &self,
+        app: &mut App,
+        sender: &Addr,
+        donor: &Addr,
+    ) -> Result<(), ContractError> {
+        app.execute_contract(
+            sender.clone(),
+            self.addr().clone(),
+            &ExecMsg::ExecuteScheduled {
+                donor: donor.to_string(),
+            },
+            &[],
+        )
+        .map_err(|err| err.downcast().unwrap())
+        .map(|_| ())
+    }
+
+    #[track_caller]
+    pub fn cancel_scheduled_donation(
+        &self,
+        app: &mut App,
+        sender: &Addr,
+    ) -> Result<(), ContractError> {
+        app.execute_contract(
+            sender.clone(),
+            self.addr().clone(),
+            &ExecMsg::CancelScheduledDonation {},
+            &[],
+        )
+        .map_err(|err| err.downcast().unwrap())
+        .map(|_| ())
+    }
+
+    // Stands in for a cw20 token `Send`, which calls `Receive` on behalf of the donor
+    #[track_caller]
+    pub fn sweep_cw20(
+        &self,
+        app: &mut App,
+        sender: &Addr,
+        token: &Addr,
+        to: &Addr,
+    ) -> Result<(), ContractError> {
+        app.execute_contract(
+            sender.clone(),
+            self.addr().clone(),
+            &ExecMsg::SweepCw20 {
+                token: token.to_string(),
+                to: to.to_string(),
+            },
+            &[],
+        )
+        .map_err(|err| err.downcast().unwrap())
+        .map(|_| ())
+    }
+
+    #[track_caller]
+    pub fn receive_cw20(
+        &self,
+        app: &mut App,
+        token: &Addr,
+        donor: &Addr,
+        amount: u128,
+    ) -> Result<(), ContractError> {
+        app.execute_contract(
+            token.clone(),
+            self.addr().clone(),
+            &ExecMsg::Receive(Cw20ReceiveMsg {
+                sender: donor.to_string(),
+                amount: amount.into(),
+                msg: Binary::default(),
+            }),
+            &[],
+        )
+        .map_err(|err| err.downcast().unwrap())
+        .map(|_| ())
+    }
+
+    #[track_caller]
+    pub fn reconcile(&self, app: &mut App, sender: &Addr) -> Result<(), ContractError> {
+        app.execute_contract(
+            sender.clone(),
+            self.addr().clone(),
+            &ExecMsg::Reconcile {},
+            &[],
+        )
+        .map_err(|err| err.downcast().unwrap())
+        .map(|_| ())
+    }
+
+    #[track_caller]
+    pub fn increment_vanity(&self, app: &mut App, sender: &Addr) -> Result<(), ContractError> {
+        app.execute_contract(
+            sender.clone(),
+            self.addr().clone(),
+            &ExecMsg::IncrementVanity {},
+            &[],
+        )
+        .map_err(|err| err.downcast().unwrap())
+        .map(|_| ())
+    }
+
+    #[track_caller]
+    pub fn claim_stipend(&self, app: &mut App, sender: &Addr) -> Result<(), ContractError> {
+        app.execute_contract(
+            sender.clone(),
+            self.addr().clone(),
+            &ExecMsg::ClaimStipend {},
+            &[],
+        )
+        .map_err(|err| err.downcast().unwrap())
+        .map(|_| ())
+    }
+
+    #[track_caller]
+    pub fn tip_last_donor(
+        &self,
+        app: &mut App,
+        sender: &Addr,
+        amount: Coin,
+    ) -> Result<(), ContractError> {
+        app.execute_contract(
+            sender.clone(),
+            self.addr().clone(),
+            &ExecMsg::TipLastDonor { amount },
+            &[],
+        )
+        .map_err(|err| err.downcast().unwrap())
+        .map(|_| ())
+    }
+
+    #[track_caller]
+    pub fn finalize_campaign(&self, app: &mut App, sender: &Addr) -> Result<(), ContractError> {
+        app.execute_contract(
+            sender.clone(),
+            self.addr().clone(),
+            &ExecMsg::FinalizeCampaign {},
+            &[],
+        )
+        .map_err(|err| err.downcast().unwrap())
+        .map(|_| ())
+    }
+
+    #[track_caller]
+    pub fn evacuate(
+        &self,
+        app: &mut App,
+        sender: &Addr,
+        to: &Addr,
+        donate: bool,
+    ) -> Result<(), ContractError> {
+        app.execute_contract(
+            sender.clone(),
+            self.addr().clone(),
+            &ExecMsg::Evacuate {
+                to: to.to_string(),
+                donate,
+            },
+            &[],
+        )
+        .map_err(|err| err.downcast().unwrap())
+        .map(|_| ())
+    }
+
+    #[track_caller]
+    pub fn query_value(&self, app: &App) -> StdResult<ValueResp> {
+        app.wrap()
+            .query_wasm_smart(self.addr().clone(), &QueryMsg::Value {})
+    }
+
+    #[track_caller]
+    pub fn query_vanity(&self, app: &App) -> StdResult<ValueResp> {
+        app.wrap()
+            .query_wasm_smart(self.addr().clone(), &QueryMsg::Vanity {})
+    }
+
+    #[track_caller]
+    pub fn query_is_allowed_donor(&self, app: &App, addr: &Addr) -> StdResult<bool> {
+        app.wrap().query_wasm_smart(
+            self.addr().clone(),
+            &QueryMsg::IsAllowedDonor {
+                addr: addr.to_string(),
+            },
+        )
+    }
+
+    #[track_caller]
+    pub fn query_total_donated_paged(
+        &self,
+        app: &App,
+        start_after: impl Into<Option<String>>,
+        limit: impl Into<Option<u32>>,
+    ) -> StdResult<Vec<Coin>> {
+        let start_after = start_after.into();
+        let limit = limit.into();
+
+        app.wrap().query_wasm_smart(
+            self.addr().clone(),
+            &QueryMsg::TotalDonatedPaged { start_after, limit },
+        )
+    }
+
+    #[track_caller]
+    pub fn query_counter_at_height(&self, app: &App, height: u64) -> StdResult<ValueResp> {
+        app.wrap()
+            .query_wasm_smart(self.addr().clone(), &QueryMsg::CounterAtHeight { height })
+    }
+
+    #[track_caller]
+    pub fn query_top_donors(
+        &self,
+        app: &App,
+        limit: impl Into<Option<u32>>,
+    ) -> StdResult<TopDonorsResp> {
+        let limit = limit.into();
+
+        app.wrap()
+            .query_wasm_smart(self.addr().clone(), &QueryMsg::TopDonors { limit })
+    }
+
+    #[track_caller]
+    pub fn query_top_donors_by_value(
+        &self,
+        app: &App,
+        limit: impl Into<Option<u32>>,
+    ) -> StdResult<TopDonorsByValueResp> {
+        let limit = limit.into();
+
+        app.wrap()
+            .query_wasm_smart(self.addr().clone(), &QueryMsg::TopDonorsByValue { limit })
+    }
+
+    #[track_caller]
+    pub fn query_schema_version(&self, app: &App) -> StdResult<SchemaVersionResp> {
+        app.wrap()
+            .query_wasm_smart(self.addr().clone(), &QueryMsg::SchemaVersion {})
+    }
+
+    #[track_caller]
+    pub fn query_goal_progress(&self, app: &App) -> StdResult<GoalProgressResp> {
+        app.wrap()
+            .query_wasm_smart(self.addr().clone(), &QueryMsg::GoalProgress {})
+    }
+
+    #[track_caller]
+    pub fn query_max_donation(&self, app: &App, denom: &str) -> StdResult<Coin> {
+        app.wrap().query_wasm_smart(
+            self.addr().clone(),
+            &QueryMsg::MaxDonation {
+                denom: denom.to_string(),
+            },
+        )
+    }
+
+    #[track_caller]
+    pub fn query_predecessor(&self, app: &App) -> StdResult<Option<Addr>> {
+        app.wrap()
+            .query_wasm_smart(self.addr().clone(), &QueryMsg::Predecessor {})
+    }
+
+    #[track_caller]
+    pub fn query_preview_migrated_state(
+        &self,
+        app: &App,
+        parent: impl Into<Option<Parent>>,
+    ) -> StdResult<MigratedStateResp> {
+        let parent = parent.into();
+
+        app.wrap().query_wasm_smart(
+            self.addr().clone(),
+            &QueryMsg::PreviewMigratedState { parent },
+        )
+    }
+
+    #[track_caller]
+    pub fn query_rounds_completed(&self, app: &App) -> StdResult<u64> {
+        app.wrap()
+            .query_wasm_smart(self.addr().clone(), &QueryMsg::RoundsCompleted {})
+    }
+
+    #[track_caller]
+    pub fn query_effective_policy(&self, app: &App) -> StdResult<EffectivePolicyResp> {
+        app.wrap()
+            .query_wasm_smart(self.addr().clone(), &QueryMsg::EffectivePolicy {})
+    }
+
+    #[track_caller]
+    pub fn query_flags(&self, app: &App) -> StdResult<FlagsResp> {
+        app.wrap()
+            .query_wasm_smart(self.addr().clone(), &QueryMsg::Flags {})
+    }
+
+    #[cfg(feature = "debug")]
+    #[track_caller]
+    pub fn query_debug(&self, app: &App) -> StdResult<crate::msg::DebugResp> {
+        app.wrap()
+            .query_wasm_smart(self.addr().clone(), &QueryMsg::Debug {})
+    }
+
+    #[track_caller]
+    pub fn query_withdrawable(&self, app: &App) -> StdResult<Vec<Coin>> {
+        app.wrap()
+            .query_wasm_smart(self.addr().clone(), &QueryMsg::Withdrawable {})
+    }
+
+    #[track_caller]
+    pub fn query_allowed_actions(&self, app: &App, addr: &Addr) -> StdResult<Vec<String>> {
+        app.wrap().query_wasm_smart(
+            self.addr().clone(),
+            &QueryMsg::AllowedActions {
+                addr: addr.to_string(),
+            },
+        )
+    }
+
+    #[track_caller]
+    pub fn query_state_size(&self, app: &App) -> StdResult<u64> {
+        app.wrap()
+            .query_wasm_smart(self.addr().clone(), &QueryMsg::StateSize {})
+    }
+
+    #[track_caller]
+    pub fn query_describe(&self, app: &App) -> StdResult<String> {
+        app.wrap()
+            .query_wasm_smart(self.addr().clone(), &QueryMsg::Describe {})
+    }
+
+    #[track_caller]
+    pub fn query_has_parent(&self, app: &App) -> StdResult<bool> {
+        app.wrap()
+            .query_wasm_smart(self.addr().clone(), &QueryMsg::HasParent {})
+    }
+
+    #[track_caller]
+    pub fn query_role(&self, app: &App) -> StdResult<RoleResp> {
+        app.wrap()
+            .query_wasm_smart(self.addr().clone(), &QueryMsg::Role {})
+    }
+
+    #[track_caller]
+    pub fn query_parent_period(&self, app: &App) -> StdResult<Option<u64>> {
+        app.wrap()
+            .query_wasm_smart(self.addr().clone(), &QueryMsg::ParentPeriod {})
+    }
+
+    #[track_caller]
+    pub fn query_needs_migration(&self, app: &App) -> StdResult<bool> {
+        app.wrap()
+            .query_wasm_smart(self.addr().clone(), &QueryMsg::NeedsMigration {})
+    }
+
+    #[track_caller]
+    pub fn query_total_withdrawn(&self, app: &App) -> StdResult<Vec<Coin>> {
+        app.wrap()
+            .query_wasm_smart(self.addr().clone(), &QueryMsg::TotalWithdrawn {})
+    }
+
+    #[track_caller]
+    pub fn query_accounting(&self, app: &App) -> StdResult<AccountingResp> {
+        app.wrap()
+            .query_wasm_smart(self.addr().clone(), &QueryMsg::Accounting {})
+    }
+
+    #[track_caller]
+    pub fn query_children(
+        &self,
+        app: &App,
+        start_after: impl Into<Option<String>>,
+        limit: impl Into<Option<u32>>,
+    ) -> StdResult<Vec<Addr>> {
+        let start_after = start_after.into();
+        let limit = limit.into();
+
+        app.wrap().query_wasm_smart(
+            self.addr().clone(),
+            &QueryMsg::Children { start_after, limit },
+        )
+    }
+
+    #[track_caller]
+    pub fn query_integrity_check(&self, app: &App) -> StdResult<IntegrityCheckResp> {
+        app.wrap()
+            .query_wasm_smart(self.addr().clone(), &QueryMsg::IntegrityCheck {})
+    }
+
+    #[track_caller]
+    pub fn query_migration_kind(&self, app: &App) -> StdResult<MigrationKind> {
+        app.wrap()
+            .query_wasm_smart(self.addr().clone(), &QueryMsg::MigrationKind {})
+    }
+
+    #[track_caller]
+    pub fn query_recent_donations(
+        &self,
+        app: &App,
+        limit: impl Into<Option<u32>>,
+    ) -> StdResult<Vec<DonationRecord>> {
+        app.wrap().query_wasm_smart(
+            self.addr().clone(),
+            &QueryMsg::RecentDonations {
+                limit: limit.into(),
+            },
+        )
+    }
+
+    #[track_caller]
+    pub fn query_cw20_policy(&self, app: &App) -> StdResult<Option<Cw20PolicyResp>> {
+        app.wrap()
+            .query_wasm_smart(self.addr().clone(), &QueryMsg::Cw20Policy {})
+    }
+
+    #[track_caller]
+    pub fn query_last_modified(&self, app: &App) -> StdResult<u64> {
+        app.wrap()
+            .query_wasm_smart(self.addr().clone(), &QueryMsg::LastModified {})
+    }
+
+    #[track_caller]
+    pub fn query_streak(&self, app: &App, addr: &str) -> StdResult<u64> {
+        app.wrap().query_wasm_smart(
+            self.addr().clone(),
+            &QueryMsg::Streak {
+                addr: addr.to_owned(),
+            },
+        )
+    }
+
+    #[track_caller]
+    pub fn query_init_params(&self, app: &App) -> StdResult<Option<InstantiateMsg>> {
+        app.wrap()
+            .query_wasm_smart(self.addr().clone(), &QueryMsg::InitParams {})
+    }
+
+    #[track_caller]
+    pub fn query_withdraw_destination(&self, app: &App) -> StdResult<Addr> {
+        app.wrap()
+            .query_wasm_smart(self.addr().clone(), &QueryMsg::WithdrawDestination {})
+    }
+
+    #[track_caller]
+    pub fn query_until_next_milestone(&self, app: &App) -> StdResult<Option<u64>> {
+        app.wrap()
+            .query_wasm_smart(self.addr().clone(), &QueryMsg::UntilNextMilestone {})
+    }
+
+    #[track_caller]
+    pub fn query_projected_at_height(&self, app: &App, height: u64) -> StdResult<ValueResp> {
+        app.wrap()
+            .query_wasm_smart(self.addr().clone(), &QueryMsg::ProjectedAtHeight { height })
+    }
+
+    #[track_caller]
+    pub fn query_receipt_count(&self, app: &App) -> StdResult<u64> {
+        app.wrap()
+            .query_wasm_smart(self.addr().clone(), &QueryMsg::ReceiptCount {})
+    }
+
+    #[track_caller]
+    pub fn query_next_forward(&self, app: &App) -> StdResult<Option<NextForwardResp>> {
+        app.wrap()
+            .query_wasm_smart(self.addr().clone(), &QueryMsg::NextForward {})
+    }
+
+    #[track_caller]
+    pub fn query_would_forward_to_parent(&self, app: &App) -> StdResult<bool> {
+        app.wrap()
+            .query_wasm_smart(self.addr().clone(), &QueryMsg::WouldForwardToParent {})
+    }
+
+    #[track_caller]
+    pub fn query_is_blocked(&self, app: &App, addr: &Addr) -> StdResult<bool> {
+        app.wrap().query_wasm_smart(
+            self.addr().clone(),
+            &QueryMsg::IsBlocked {
+                addr: addr.to_string(),
+            },
+        )
     }
 }
 
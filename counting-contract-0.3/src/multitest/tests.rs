@@ -1,10 +1,28 @@
 use crate::{
-    msg::{Parent, ValueResp},
-    state::{ParentDonation, PARENT_DONATION},
+    contract, execute, instantiate,
+    msg::{
+        api, AccountingResp, Cw20BalanceResponse, Cw20ExecuteMsg, Cw20PolicyResp, Cw20QueryMsg,
+        Cw20ReceiveMsg, DonationRecord, DonorCount, DonorTotal, EffectivePolicyResp, ExecMsg,
+        FlagsResp, GoalProgressResp, InstantiateMsg, IntegrityCheckResp, MigrateMsg, MigrateResp,
+        MigratedStateResp, MigrationKind, NextForwardResp, Parent, Payout, RoleResp,
+        SchemaVersionResp, ValueResp, WithdrawResp,
+    },
+    query,
+    state::{
+        ParentDonation, CW20_COUNTER, DONOR_COUNTS, DONOR_TOTALS, MAX_DONATION, PARENT_DONATION,
+        TOTAL_DONATED,
+    },
+};
+use cosmwasm_std::testing::{
+    mock_dependencies, mock_dependencies_with_balance, mock_env, mock_info,
+};
+use cosmwasm_std::{
+    coin, coins, from_slice, to_binary, Addr, BankMsg, Binary, Coin, Decimal, Deps, DepsMut, Empty,
+    Env, Event, MessageInfo, Response, StdResult, Storage, SubMsg, Uint128, WasmMsg,
 };
-use cosmwasm_std::{coin, coins, Addr, Decimal};
 use counting_contract_0_1::multitest::contract::CountingContract as CountingContract_0_1;
-use cw_multi_test::App;
+use cw_multi_test::{App, ContractWrapper, Executor};
+use cw_storage_plus::Map;
 
 use crate::{
     error::ContractError,
@@ -14,6 +32,8 @@ use crate::{
 use super::contract::CountingContract;
 
 const ATOM: &str = "atom";
+const JUNO: &str = "juno";
+const OSMO: &str = "osmo";
 
 #[test]
 fn query_value() {
@@ -109,10 +129,15 @@ fn donate_with_funds() {
 }
 
 #[test]
-fn donate_expecting_no_funds() {
+fn donate_exact_threshold() {
     let sender = Addr::unchecked("sender");
 
-    let mut app = App::default();
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, coins(100, ATOM))
+            .unwrap();
+    });
 
     let code_id = CountingContract::store_code(&mut app);
 
@@ -123,21 +148,30 @@ fn donate_expecting_no_funds() {
         "Counting contract",
         None,
         None,
-        coin(0, ATOM),
+        coin(100, ATOM),
         None,
     )
     .unwrap();
 
-    // execute donate
-    contract.donate(&mut app, &sender, &[]).unwrap();
+    let resp = app
+        .execute_contract(
+            sender.clone(),
+            contract.addr().clone(),
+            &ExecMsg::Donate {
+                forward_depth: None,
+                idempotency_key: None,
+            },
+            &coins(100, ATOM),
+        )
+        .unwrap();
+    resp.assert_event(&Event::new("wasm").add_attribute("counted", "true"));
 
     let resp = contract.query_value(&app).unwrap();
-
-    assert_eq!(resp.value, 1);
+    assert_eq!(resp, ValueResp { value: 1 });
 }
 
 #[test]
-fn reset() {
+fn donate_reports_contract_version() {
     let sender = Addr::unchecked("sender");
 
     let mut app = App::default();
@@ -156,129 +190,158 @@ fn reset() {
     )
     .unwrap();
 
-    // execute reset
-    contract.reset(&mut app, &sender, 10).unwrap();
-
-    let resp = contract.query_value(&app).unwrap();
-
-    assert_eq!(resp.value, 10);
+    let resp = app
+        .execute_contract(
+            sender,
+            contract.addr().clone(),
+            &ExecMsg::Donate {
+                forward_depth: None,
+                idempotency_key: None,
+            },
+            &[],
+        )
+        .unwrap();
+    resp.assert_event(
+        &Event::new("wasm").add_attribute("contract_version", env!("CARGO_PKG_VERSION")),
+    );
 }
 
 #[test]
-fn withdraw() {
+fn donate_within_tolerance() {
     let sender = Addr::unchecked("sender");
-    let owner = Addr::unchecked("owner");
 
     let mut app = App::new(|router, _api, storage| {
         router
             .bank
-            .init_balance(storage, &sender, coins(10, ATOM))
+            .init_balance(storage, &sender, coins(17, ATOM))
             .unwrap();
     });
 
     let code_id = CountingContract::store_code(&mut app);
 
-    let contract = CountingContract::instantiate(
+    let contract = CountingContract::instantiate_with_msg(
         &mut app,
         code_id,
-        &owner,
+        &sender,
         "Counting contract",
         None,
-        None,
-        coin(10, ATOM),
-        None,
+        &InstantiateMsg {
+            minimal_donation: coin(10, ATOM),
+            tolerance: Some(Uint128::new(1)),
+            ..Default::default()
+        },
     )
     .unwrap();
 
-    // execute donate (sender)
-    contract
-        .donate(&mut app, &sender, &coins(10, ATOM))
-        .unwrap();
-
-    // execute donate (owner)
-    contract.donate(&mut app, &sender, &[]).unwrap();
+    contract.donate(&mut app, &sender, &coins(9, ATOM)).unwrap();
 
-    // execute withdraw
-    contract.withdraw(&mut app, &owner).unwrap();
+    let resp = contract.query_value(&app).unwrap();
+    assert_eq!(resp, ValueResp { value: 1 });
 
-    assert_eq!(
-        app.wrap().query_all_balances(owner).unwrap(),
-        coins(10, ATOM)
-    );
+    contract.donate(&mut app, &sender, &coins(8, ATOM)).unwrap();
 
-    assert_eq!(app.wrap().query_all_balances(sender).unwrap(), vec![]);
-    assert_eq!(
-        app.wrap()
-            .query_all_balances(contract.addr().clone())
-            .unwrap(),
-        vec![]
-    );
+    let resp = contract.query_value(&app).unwrap();
+    assert_eq!(resp, ValueResp { value: 1 });
 }
 
 #[test]
-fn withdraw_to() {
-    let owner = Addr::unchecked("owner");
+fn donate_milestone() {
     let sender = Addr::unchecked("sender");
-    let receiver = Addr::unchecked("receiver");
 
     let mut app = App::new(|router, _api, storage| {
         router
             .bank
-            .init_balance(storage, &sender, coins(10, ATOM))
+            .init_balance(storage, &sender, coins(60, ATOM))
             .unwrap();
     });
 
     let code_id = CountingContract::store_code(&mut app);
 
-    let contract = CountingContract::instantiate(
+    let contract = CountingContract::instantiate_with_msg(
         &mut app,
         code_id,
-        &owner,
+        &sender,
         "Counting contract",
         None,
-        None,
-        coin(10, ATOM),
-        None,
+        &InstantiateMsg {
+            minimal_donation: coin(10, ATOM),
+            milestone_size: Some(5),
+            ..Default::default()
+        },
     )
     .unwrap();
 
-    // execute donate (sender)
-    contract
-        .donate(&mut app, &sender, &coins(10, ATOM))
-        .unwrap();
+    for counter in 1..=6 {
+        let resp = app
+            .execute_contract(
+                sender.clone(),
+                contract.addr().clone(),
+                &ExecMsg::Donate {
+                    forward_depth: None,
+                    idempotency_key: None,
+                },
+                &coins(10, ATOM),
+            )
+            .unwrap();
 
-    // execute withdraw_to (owner -> receiver)
-    contract
-        .withdraw_to(&mut app, &owner, &receiver, coins(5, ATOM))
-        .unwrap();
+        let milestones: Vec<_> = resp
+            .events
+            .iter()
+            .flat_map(|event| &event.attributes)
+            .filter(|attr| attr.key == "milestone")
+            .map(|attr| attr.value.as_str())
+            .collect();
 
-    assert_eq!(app.wrap().query_all_balances(owner).unwrap(), vec![]);
-    assert_eq!(app.wrap().query_all_balances(sender).unwrap(), vec![]);
-    assert_eq!(
-        app.wrap().query_all_balances(receiver).unwrap(),
-        coins(5, ATOM)
-    );
-    assert_eq!(
-        app.wrap()
-            .query_all_balances(contract.addr().clone())
-            .unwrap(),
-        coins(5, ATOM)
-    );
+        if counter == 5 {
+            assert_eq!(milestones, vec!["5"]);
+        } else {
+            assert!(milestones.is_empty());
+        }
+    }
 }
 
 #[test]
-fn unauthorized_withdraw() {
-    let owner = Addr::unchecked("owner");
-    let member = Addr::unchecked("member");
+fn until_next_milestone() {
+    let sender = Addr::unchecked("sender");
 
-    let mut app = App::default();
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, coins(30, ATOM))
+            .unwrap();
+    });
 
     let code_id = CountingContract::store_code(&mut app);
 
+    let contract = CountingContract::instantiate_with_msg(
+        &mut app,
+        code_id,
+        &sender,
+        "Counting contract",
+        None,
+        &InstantiateMsg {
+            counter: 7,
+            minimal_donation: coin(10, ATOM),
+            milestone_size: Some(10),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    assert_eq!(contract.query_until_next_milestone(&app).unwrap(), Some(3));
+
+    for _ in 0..3 {
+        contract
+            .donate(&mut app, &sender, &coins(10, ATOM))
+            .unwrap();
+    }
+
+    assert_eq!(contract.query_until_next_milestone(&app).unwrap(), Some(10));
+
     let contract = CountingContract::instantiate(
         &mut app,
         code_id,
-        &owner,
+        &sender,
         "Counting contract",
         None,
         None,
@@ -287,257 +350,6750 @@ fn unauthorized_withdraw() {
     )
     .unwrap();
 
-    let err = contract.withdraw(&mut app, &member).unwrap_err();
-    assert_eq!(
-        err,
-        ContractError::Unauthorized {
-            owner: owner.into()
-        }
-    );
+    assert_eq!(contract.query_until_next_milestone(&app).unwrap(), None);
 }
 
 #[test]
-fn unauthorized_withdraw_to() {
-    let owner = Addr::unchecked("owner");
-    let member = Addr::unchecked("member");
+fn donate_receipts() {
+    let sender = Addr::unchecked("sender");
 
-    let mut app = App::default();
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, coins(25, ATOM))
+            .unwrap();
+    });
 
     let code_id = CountingContract::store_code(&mut app);
 
     let contract = CountingContract::instantiate(
         &mut app,
         code_id,
-        &owner,
+        &sender,
         "Counting contract",
         None,
         None,
-        coin(0, ATOM),
+        coin(10, ATOM),
         None,
     )
     .unwrap();
 
-    let err = contract
-        .withdraw_to(&mut app, &member, &owner, None)
-        .unwrap_err();
+    assert_eq!(contract.query_receipt_count(&app).unwrap(), 0);
 
-    assert_eq!(
-        err,
-        ContractError::Unauthorized {
-            owner: owner.into()
-        }
-    );
+    for (receipt_id, amount) in [(1, 10), (2, 15)] {
+        let resp = app
+            .execute_contract(
+                sender.clone(),
+                contract.addr().clone(),
+                &ExecMsg::Donate {
+                    forward_depth: None,
+                    idempotency_key: None,
+                },
+                &coins(amount, ATOM),
+            )
+            .unwrap();
+        resp.assert_event(
+            &Event::new("wasm-receipt")
+                .add_attribute("receipt_id", receipt_id.to_string())
+                .add_attribute("donor", sender.as_str())
+                .add_attribute("amount", coin(amount, ATOM).to_string()),
+        );
+    }
+
+    assert_eq!(contract.query_receipt_count(&app).unwrap(), 2);
 }
 
 #[test]
-fn unauthorized_reset() {
-    let owner = Addr::unchecked("owner");
-    let member = Addr::unchecked("member");
+fn donate_minimal_donation_bps() {
+    let sender = Addr::unchecked("sender");
 
-    let mut app = App::default();
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, coins(100, ATOM))
+            .unwrap();
+    });
 
     let code_id = CountingContract::store_code(&mut app);
 
-    let contract = CountingContract::instantiate(
+    // at least 10% of the donor's balance
+    let contract = CountingContract::instantiate_with_msg(
         &mut app,
         code_id,
-        &owner,
+        &sender,
         "Counting contract",
         None,
-        None,
-        coin(0, ATOM),
-        None,
+        &InstantiateMsg {
+            minimal_donation: coin(1000, ATOM),
+            minimal_donation_bps: true,
+            ..Default::default()
+        },
     )
     .unwrap();
 
-    let err = contract.reset(&mut app, &member, 10).unwrap_err();
+    // 9 out of 100
+    contract.donate(&mut app, &sender, &coins(9, ATOM)).unwrap();
+    let resp = contract.query_value(&app).unwrap();
+    assert_eq!(resp, ValueResp { value: 0 });
 
-    assert_eq!(
-        err,
-        ContractError::Unauthorized {
-            owner: owner.into()
-        }
-    );
+    // 10 out of the 91 left
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+    let resp = contract.query_value(&app).unwrap();
+    assert_eq!(resp, ValueResp { value: 1 });
+
+    // 8 out of the 81 left
+    contract.donate(&mut app, &sender, &coins(8, ATOM)).unwrap();
+    let resp = contract.query_value(&app).unwrap();
+    assert_eq!(resp, ValueResp { value: 2 });
 }
 
 #[test]
-fn migration() {
-    let admin = Addr::unchecked("admin");
-    let owner = Addr::unchecked("owner");
+fn scheduled_donation_minimal_donation_bps() {
     let sender = Addr::unchecked("sender");
+    let cranker = Addr::unchecked("cranker");
 
     let mut app = App::new(|router, _api, storage| {
         router
             .bank
-            .init_balance(storage, &sender, coins(10, ATOM))
-            .unwrap()
+            .init_balance(storage, &sender, coins(100, ATOM))
+            .unwrap();
     });
 
-    let old_code_id = CountingContract_0_1::store_code(&mut app);
-    let new_code_id = CountingContract::store_code(&mut app);
+    let code_id = CountingContract::store_code(&mut app);
 
-    let contract = CountingContract_0_1::instantiate(
+    // at least 10% of the donor's balance
+    let contract = CountingContract::instantiate_with_msg(
         &mut app,
-        old_code_id,
-        &owner,
+        code_id,
+        &sender,
         "Counting contract",
-        &admin,
         None,
-        coin(10, ATOM),
+        &InstantiateMsg {
+            minimal_donation: coin(1000, ATOM),
+            minimal_donation_bps: true,
+            ..Default::default()
+        },
     )
     .unwrap();
 
     contract
-        .donate(&mut app, &sender, &coins(10, ATOM))
+        .schedule_donation(&mut app, &sender, 5, coin(9, ATOM), &coins(9, ATOM))
         .unwrap();
 
-    let contract =
-        CountingContract::migrate(&mut app, contract.into(), new_code_id, &admin, None).unwrap();
-
+    // 9 out of the 91 left, the escrowed deposit isn't counted as the donor's balance again
+    app.update_block(|block| block.height += 5);
+    contract
+        .execute_scheduled(&mut app, &cranker, &sender)
+        .unwrap();
     let resp = contract.query_value(&app).unwrap();
     assert_eq!(resp, ValueResp { value: 1 });
-
-    let state = STATE.query(&app.wrap(), contract.addr().clone()).unwrap();
-    assert_eq!(
-        state,
-        State {
-            counter: 1,
-            minimal_donation: coin(10, ATOM),
-            owner,
-            donating_parent: None
-        }
-    );
 }
 
 #[test]
-fn migration_with_parent() {
-    let admin = Addr::unchecked("admin");
-    let owner = Addr::unchecked("owner");
+fn donate_idempotency_key() {
     let sender = Addr::unchecked("sender");
-    let parent = Addr::unchecked("parent");
 
     let mut app = App::new(|router, _api, storage| {
         router
             .bank
-            .init_balance(storage, &sender, coins(10, ATOM))
-            .unwrap()
+            .init_balance(storage, &sender, coins(30, ATOM))
+            .unwrap();
     });
 
-    let old_code_id = CountingContract_0_1::store_code(&mut app);
-    let new_code_id = CountingContract::store_code(&mut app);
+    let code_id = CountingContract::store_code(&mut app);
 
-    let contract = CountingContract_0_1::instantiate(
+    let contract = CountingContract::instantiate(
         &mut app,
-        old_code_id,
-        &owner,
+        code_id,
+        &sender,
         "Counting contract",
-        &admin,
+        None,
         None,
         coin(10, ATOM),
+        None,
     )
     .unwrap();
 
     contract
-        .donate(&mut app, &sender, &coins(10, ATOM))
+        .donate_with_key(&mut app, &sender, &coins(10, ATOM), "relay-1")
         .unwrap();
 
-    let contract = CountingContract::migrate(
-        &mut app,
-        contract.into(),
-        new_code_id,
-        &admin,
-        Parent {
-            addr: parent.to_string(),
-            donating_period: 2,
-            part: Decimal::percent(10),
-        },
-    )
-    .unwrap();
-
-    let resp = contract.query_value(&app).unwrap();
-    assert_eq!(resp, ValueResp { value: 1 });
-
-    let state = STATE.query(&app.wrap(), contract.addr().clone()).unwrap();
+    let err = contract
+        .donate_with_key(&mut app, &sender, &coins(10, ATOM), "relay-1")
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::DuplicateDonation {
+            key: "relay-1".to_owned()
+        }
+    );
+
+    contract
+        .donate_with_key(&mut app, &sender, &coins(10, ATOM), "relay-2")
+        .unwrap();
+
+    let resp = contract.query_value(&app).unwrap();
+    assert_eq!(resp, ValueResp { value: 2 });
+    assert_eq!(
+        app.wrap().query_all_balances(&sender).unwrap(),
+        coins(10, ATOM)
+    );
+}
+
+#[test]
+fn max_donation() {
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, coins(100, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &sender,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        None,
+    )
+    .unwrap();
+
+    let resp = contract.query_max_donation(&app, ATOM).unwrap();
+    assert_eq!(resp, coin(0, ATOM));
+
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+    contract
+        .donate(&mut app, &sender, &coins(40, ATOM))
+        .unwrap();
+    contract
+        .donate(&mut app, &sender, &coins(20, ATOM))
+        .unwrap();
+    // too small to qualify, so it is not recorded
+    contract.donate(&mut app, &sender, &coins(5, ATOM)).unwrap();
+
+    let resp = contract.query_max_donation(&app, ATOM).unwrap();
+    assert_eq!(resp, coin(40, ATOM));
+
+    let resp = contract.query_max_donation(&app, JUNO).unwrap();
+    assert_eq!(resp, coin(0, JUNO));
+}
+
+#[test]
+fn event_prefix() {
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, coins(10, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate_with_msg(
+        &mut app,
+        code_id,
+        &sender,
+        "Counting contract",
+        None,
+        &InstantiateMsg {
+            minimal_donation: coin(10, ATOM),
+            event_prefix: Some("counting".to_owned()),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    let resp = app
+        .execute_contract(
+            sender.clone(),
+            contract.addr().clone(),
+            &ExecMsg::Donate {
+                forward_depth: None,
+                idempotency_key: None,
+            },
+            &coins(10, ATOM),
+        )
+        .unwrap();
+
+    resp.assert_event(
+        &Event::new("wasm")
+            .add_attribute("counting/action", "donate")
+            .add_attribute("counting/sender", sender.as_str())
+            .add_attribute("counting/counter", "1"),
+    );
+    assert!(!resp.has_event(&Event::new("wasm").add_attribute("action", "donate")));
+
+    resp.assert_event(
+        &Event::new("wasm-receipt")
+            .add_attribute("counting/receipt_id", "1")
+            .add_attribute("counting/donor", sender.as_str()),
+    );
+    assert!(!resp.has_event(&Event::new("wasm-receipt").add_attribute("receipt_id", "1")));
+}
+
+#[test]
+fn donate_not_counted_reason() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, vec![coin(5, ATOM), coin(10, JUNO)])
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        None,
+    )
+    .unwrap();
+
+    let resp = app
+        .execute_contract(
+            sender.clone(),
+            contract.addr().clone(),
+            &ExecMsg::Donate {
+                forward_depth: None,
+                idempotency_key: None,
+            },
+            &coins(10, JUNO),
+        )
+        .unwrap();
+
+    resp.assert_event(
+        &Event::new("wasm")
+            .add_attribute("reason", "wrong_denom")
+            .add_attribute("counted", "false"),
+    );
+
+    let resp = app
+        .execute_contract(
+            sender,
+            contract.addr().clone(),
+            &ExecMsg::Donate {
+                forward_depth: None,
+                idempotency_key: None,
+            },
+            &coins(5, ATOM),
+        )
+        .unwrap();
+
+    resp.assert_event(
+        &Event::new("wasm")
+            .add_attribute("reason", "below_threshold")
+            .add_attribute("counted", "false"),
+    );
+
+    let resp = contract.query_value(&app).unwrap();
+    assert_eq!(resp, ValueResp { value: 0 });
+}
+
+#[test]
+fn recent_donations() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::default();
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(0, ATOM),
+        None,
+    )
+    .unwrap();
+
+    assert_eq!(contract.query_recent_donations(&app, None).unwrap(), vec![]);
+
+    let first_height = app.block_info().height;
+    for _ in 0..52 {
+        contract.donate(&mut app, &sender, &[]).unwrap();
+        app.update_block(|block| block.height += 1);
+    }
+
+    let donations = contract.query_recent_donations(&app, 100).unwrap();
+    assert_eq!(donations.len(), 50);
+    assert_eq!(
+        donations[0],
+        DonationRecord {
+            donor: sender.clone(),
+            amount: vec![],
+            height: first_height + 51,
+            counted: true,
+        }
+    );
+    // the two oldest donations were overwritten
+    assert_eq!(donations[49].height, first_height + 2);
+
+    let donations = contract.query_recent_donations(&app, 2).unwrap();
+    let heights: Vec<_> = donations.iter().map(|donation| donation.height).collect();
+    assert_eq!(heights, vec![first_height + 51, first_height + 50]);
+}
+
+#[test]
+fn counter_at_height() {
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, coins(20, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &sender,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        None,
+    )
+    .unwrap();
+
+    let first_height = app.block_info().height;
+
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+
+    app.update_block(|block| block.height += 10);
+
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+
+    let resp = contract
+        .query_counter_at_height(&app, first_height)
+        .unwrap();
+    assert_eq!(resp, ValueResp { value: 1 });
+
+    // no snapshot was recorded in between, so the earlier one is used
+    let resp = contract
+        .query_counter_at_height(&app, first_height + 5)
+        .unwrap();
+    assert_eq!(resp, ValueResp { value: 1 });
+
+    let resp = contract
+        .query_counter_at_height(&app, first_height + 10)
+        .unwrap();
+    assert_eq!(resp, ValueResp { value: 2 });
+
+    contract
+        .query_counter_at_height(&app, first_height - 1)
+        .unwrap_err();
+}
+
+#[test]
+fn donate_duplicated_denoms() {
+    let mut deps = mock_dependencies();
+
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        InstantiateMsg {
+            minimal_donation: coin(10, ATOM),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    // none of the coins reaches the minimal donation on its own
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("sender", &[coin(6, ATOM), coin(6, ATOM), coin(3, JUNO)]),
+        ExecMsg::Donate {
+            forward_depth: None,
+            idempotency_key: None,
+        },
+    )
+    .unwrap();
+
+    let state = STATE.load(&deps.storage).unwrap();
+    assert_eq!(state.counter, 1);
+
+    let total = TOTAL_DONATED.load(&deps.storage, ATOM).unwrap();
+    assert_eq!(total, Uint128::new(12));
+    let total = TOTAL_DONATED.load(&deps.storage, JUNO).unwrap();
+    assert_eq!(total, Uint128::new(3));
+
+    let max = MAX_DONATION.load(&deps.storage, ATOM).unwrap();
+    assert_eq!(max, Uint128::new(12));
+}
+
+#[test]
+fn donate_overdue_parent_countdown() {
+    let mut deps = mock_dependencies_with_balance(&coins(10, ATOM));
+
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        InstantiateMsg {
+            minimal_donation: coin(10, ATOM),
+            parent: Some(Parent {
+                addr: "parent".to_owned(),
+                donating_period: 2,
+                part: Decimal::percent(10),
+                fee: None,
+            }),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    // a buggy migration could leave the countdown at zero
+    STATE
+        .update(&mut deps.storage, |mut state| -> StdResult<_> {
+            state.donating_parent = Some(0);
+            Ok(state)
+        })
+        .unwrap();
+
+    let resp = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("sender", &coins(10, ATOM)),
+        ExecMsg::Donate {
+            forward_depth: None,
+            idempotency_key: None,
+        },
+    )
+    .unwrap();
+
+    assert_eq!(
+        resp.messages,
+        vec![SubMsg::reply_on_success(
+            WasmMsg::Execute {
+                contract_addr: "parent".to_owned(),
+                msg: to_binary(&ExecMsg::Donate {
+                    forward_depth: Some(1),
+                    idempotency_key: None,
+                })
+                .unwrap(),
+                funds: coins(1, ATOM),
+            },
+            contract::FORWARD_REPLY_ID,
+        )]
+    );
+
+    let state = STATE.load(&deps.storage).unwrap();
+    assert_eq!(state.donating_parent, Some(2));
+}
+
+#[test]
+fn send_if_nonempty() {
+    let to = Addr::unchecked("receiver");
+
+    assert_eq!(contract::send_if_nonempty(&to, vec![]), None);
+    assert_eq!(
+        contract::send_if_nonempty(&to, vec![coin(0, ATOM), coin(0, JUNO)]),
+        None
+    );
+    assert_eq!(
+        contract::send_if_nonempty(&to, vec![coin(0, ATOM), coin(5, JUNO)]),
+        Some(BankMsg::Send {
+            to_address: "receiver".to_owned(),
+            amount: coins(5, JUNO),
+        })
+    );
+}
+
+#[test]
+fn donate_exact_with_refund_excess() {
+    let mut deps = mock_dependencies();
+
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        InstantiateMsg {
+            minimal_donation: coin(10, ATOM),
+            refund_excess: true,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    // nothing exceeds the minimal donation, so there is nothing to send back
+    let resp = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("sender", &coins(10, ATOM)),
+        ExecMsg::Donate {
+            forward_depth: None,
+            idempotency_key: None,
+        },
+    )
+    .unwrap();
+
+    assert!(resp.messages.is_empty());
+}
+
+#[test]
+fn donate_threshold_is_numeric() {
+    let mut deps = mock_dependencies();
+
+    // "0020" is the same amount as "20", only written differently
+    let minimal_donation: Coin = from_slice(br#"{"denom":"atom","amount":"0020"}"#).unwrap();
+
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        InstantiateMsg {
+            minimal_donation,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    // "9" and "100" would compare the other way round as strings
+    for (amount, counted) in [(9, false), (20, true), (100, true)] {
+        let resp = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("sender", &coins(amount, ATOM)),
+            ExecMsg::Donate {
+                forward_depth: None,
+                idempotency_key: None,
+            },
+        )
+        .unwrap();
+
+        assert!(resp
+            .attributes
+            .iter()
+            .any(|attr| attr.key == "counted" && attr.value == counted.to_string()));
+    }
+
+    assert_eq!(STATE.load(&deps.storage).unwrap().counter, 2);
+}
+
+#[test]
+fn donate_total_overflow() {
+    let mut deps = mock_dependencies();
+
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        InstantiateMsg {
+            minimal_donation: coin(10, ATOM),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    TOTAL_DONATED
+        .save(&mut deps.storage, ATOM, &(Uint128::MAX - Uint128::new(5)))
+        .unwrap();
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("sender", &coins(10, ATOM)),
+        ExecMsg::Donate {
+            forward_depth: None,
+            idempotency_key: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::AccountingOverflow {});
+
+    let state = STATE.load(&deps.storage).unwrap();
+    assert_eq!(state.counter, 0);
+}
+
+#[test]
+fn recompute_stats_reports_drift() {
+    let mut deps = mock_dependencies();
+
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        InstantiateMsg {
+            minimal_donation: coin(10, ATOM),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    for _ in 0..2 {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("sender", &coins(10, ATOM)),
+            ExecMsg::Donate {
+                forward_depth: None,
+                idempotency_key: None,
+            },
+        )
+        .unwrap();
+    }
+
+    let resp = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecMsg::RecomputeStats {},
+    )
+    .unwrap();
+    assert!(!resp.attributes.iter().any(|attr| attr.key == "drift"));
+
+    // corrupt the donor count behind the contract's back
+    DONOR_COUNTS
+        .save(&mut deps.storage, &Addr::unchecked("sender"), &5)
+        .unwrap();
+
+    let resp = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecMsg::RecomputeStats {},
+    )
+    .unwrap();
+    assert!(resp
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "drift" && attr.value == "-3"));
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("sender", &[]),
+        ExecMsg::RecomputeStats {},
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Unauthorized {
+            owner: "owner".to_string()
+        }
+    );
+}
+
+#[test]
+fn donate_free_without_accepting_funds() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, coins(20, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(0, ATOM),
+        None,
+    )
+    .unwrap();
+
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+
+    assert_eq!(
+        app.wrap().query_all_balances(contract.addr()).unwrap(),
+        coins(10, ATOM)
+    );
+
+    contract
+        .set_accept_funds_when_free(&mut app, &owner, false)
+        .unwrap();
+
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+
+    let resp = contract.query_value(&app).unwrap();
+    assert_eq!(resp, ValueResp { value: 2 });
+
+    assert_eq!(
+        app.wrap().query_all_balances(&sender).unwrap(),
+        coins(10, ATOM)
+    );
+    assert_eq!(
+        app.wrap().query_all_balances(contract.addr()).unwrap(),
+        coins(10, ATOM)
+    );
+
+    let err = contract
+        .set_accept_funds_when_free(&mut app, &sender, true)
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Unauthorized {
+            owner: owner.to_string()
+        }
+    );
+}
+
+#[test]
+fn donate_matching() {
+    let donor = Addr::unchecked("donor");
+    let matcher = Addr::unchecked("matcher");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &donor, coins(25, ATOM))
+            .unwrap();
+        router
+            .bank
+            .init_balance(storage, &matcher, coins(40, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &donor,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        None,
+    )
+    .unwrap();
+
+    let err = contract
+        .donate_matching(&mut app, &matcher, &donor, &coins(25, ATOM))
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::UnknownDonor {
+            donor: donor.to_string()
+        }
+    );
+
+    contract.donate(&mut app, &donor, &coins(25, ATOM)).unwrap();
+
+    let err = contract
+        .donate_matching(&mut app, &matcher, &donor, &coins(15, ATOM))
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::DonationTooSmall {
+            required: coin(25, ATOM).to_string()
+        }
+    );
+
+    contract
+        .donate_matching(&mut app, &matcher, &donor, &coins(25, ATOM))
+        .unwrap();
+
+    let resp = contract.query_value(&app).unwrap();
+    assert_eq!(resp, ValueResp { value: 2 });
+
+    assert_eq!(
+        app.wrap().query_all_balances(&matcher).unwrap(),
+        coins(15, ATOM)
+    );
+}
+
+#[test]
+fn donate_refund_excess() {
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, coins(25, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate_with_msg(
+        &mut app,
+        code_id,
+        &sender,
+        "Counting contract",
+        None,
+        &InstantiateMsg {
+            minimal_donation: coin(10, ATOM),
+            refund_excess: true,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    contract
+        .donate(&mut app, &sender, &coins(25, ATOM))
+        .unwrap();
+
+    let resp = contract.query_value(&app).unwrap();
+    assert_eq!(resp, ValueResp { value: 1 });
+
+    assert_eq!(
+        app.wrap().query_all_balances(&sender).unwrap(),
+        coins(15, ATOM)
+    );
+    assert_eq!(
+        app.wrap().query_all_balances(contract.addr()).unwrap(),
+        coins(10, ATOM)
+    );
+}
+
+#[test]
+fn donate_round_completed() {
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, coins(40, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate_with_msg(
+        &mut app,
+        code_id,
+        &sender,
+        "Counting contract",
+        None,
+        &InstantiateMsg {
+            minimal_donation: coin(10, ATOM),
+            round_size: Some(3),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+
+    let resp = app
+        .execute_contract(
+            sender.clone(),
+            contract.addr().clone(),
+            &ExecMsg::Donate {
+                forward_depth: None,
+                idempotency_key: None,
+            },
+            &coins(10, ATOM),
+        )
+        .unwrap();
+    resp.assert_event(&Event::new("wasm-round_completed").add_attribute("round", "1"));
+
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+
+    let resp = contract.query_value(&app).unwrap();
+    assert_eq!(resp, ValueResp { value: 1 });
+
+    let resp = contract.query_rounds_completed(&app).unwrap();
+    assert_eq!(resp, 1);
+}
+
+#[test]
+fn update_config() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, coins(10, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        None,
+    )
+    .unwrap();
+
+    let err = contract
+        .update_config(&mut app, &sender, None, true, None)
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Unauthorized {
+            owner: owner.to_string()
+        }
+    );
+
+    contract
+        .update_config(&mut app, &owner, None, true, 5)
+        .unwrap();
+
+    let err = contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap_err();
+    assert_eq!(err, ContractError::Paused {});
+
+    let resp = contract.query_goal_progress(&app).unwrap();
+    assert_eq!(resp.goal, 5);
+
+    let state = STATE.query(&app.wrap(), contract.addr().clone()).unwrap();
+    assert_eq!(state.minimal_donation, coin(10, ATOM));
+
+    contract
+        .update_config(&mut app, &owner, None, false, None)
+        .unwrap();
+
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+
+    let resp = contract.query_value(&app).unwrap();
+    assert_eq!(resp, ValueResp { value: 1 });
+
+    // A goal the counter already passed
+    let err = contract
+        .update_config(&mut app, &owner, None, None, 0)
+        .unwrap_err();
+    assert_eq!(err, ContractError::CounterTooHigh { max: 0 });
+
+    // Changing the denom here reports the stale balance just like `SetMinimalDonation`
+    let resp = app
+        .execute_contract(
+            owner.clone(),
+            contract.addr().clone(),
+            &ExecMsg::UpdateConfig {
+                minimal_donation: Some(coin(10, JUNO)),
+                paused: None,
+                max_counter: None,
+            },
+            &[],
+        )
+        .unwrap();
+    resp.assert_event(
+        &Event::new("wasm").add_attribute("stale_denom_balance", coin(10, ATOM).to_string()),
+    );
+}
+
+#[test]
+fn request_refund() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+    let other = Addr::unchecked("other");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, coins(20, ATOM))
+            .unwrap();
+        router
+            .bank
+            .init_balance(storage, &other, coins(10, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        None,
+    )
+    .unwrap();
+
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+    contract.donate(&mut app, &other, &coins(10, ATOM)).unwrap();
+
+    let err = contract.request_refund(&mut app, &sender).unwrap_err();
+    assert_eq!(err, ContractError::NotRefundable {});
+
+    let err = contract
+        .set_refundable(&mut app, &sender, true)
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Unauthorized {
+            owner: owner.to_string()
+        }
+    );
+
+    contract.set_refundable(&mut app, &owner, true).unwrap();
+    contract.request_refund(&mut app, &sender).unwrap();
+
+    assert_eq!(
+        app.wrap().query_all_balances(&sender).unwrap(),
+        coins(20, ATOM)
+    );
+    assert_eq!(
+        app.wrap().query_all_balances(contract.addr()).unwrap(),
+        coins(10, ATOM)
+    );
+
+    let resp = contract.query_value(&app).unwrap();
+    assert_eq!(resp, ValueResp { value: 1 });
+    assert_eq!(
+        DONOR_COUNTS
+            .query(&app.wrap(), contract.addr().clone(), &sender)
+            .unwrap(),
+        None
+    );
+    assert_eq!(
+        contract
+            .query_total_donated_paged(&app, None, None)
+            .unwrap(),
+        coins(10, ATOM)
+    );
+
+    let err = contract.request_refund(&mut app, &sender).unwrap_err();
+    assert_eq!(err, ContractError::NothingToRefund {});
+}
+
+#[test]
+fn request_refund_after_withdraw() {
+    let owner = Addr::unchecked("owner");
+    let alice = Addr::unchecked("alice");
+    let bob = Addr::unchecked("bob");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &alice, coins(10, ATOM))
+            .unwrap();
+        router
+            .bank
+            .init_balance(storage, &bob, coins(10, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        None,
+    )
+    .unwrap();
+
+    contract.donate(&mut app, &alice, &coins(10, ATOM)).unwrap();
+    contract.withdraw(&mut app, &owner).unwrap();
+    contract.donate(&mut app, &bob, &coins(10, ATOM)).unwrap();
+
+    contract.set_refundable(&mut app, &owner, true).unwrap();
+
+    // Alice's donation was withdrawn, what is held now is Bob's
+    let err = contract.request_refund(&mut app, &alice).unwrap_err();
+    assert_eq!(err, ContractError::NothingToRefund {});
+
+    contract.request_refund(&mut app, &bob).unwrap();
+
+    assert_eq!(app.wrap().query_all_balances(&alice).unwrap(), vec![]);
+    assert_eq!(
+        app.wrap().query_all_balances(&bob).unwrap(),
+        coins(10, ATOM)
+    );
+    assert_eq!(
+        app.wrap().query_all_balances(contract.addr()).unwrap(),
+        vec![]
+    );
+}
+
+#[test]
+fn request_refund_keeps_withdrawn_total() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, coins(30, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        None,
+    )
+    .unwrap();
+
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+    contract.withdraw(&mut app, &owner).unwrap();
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+
+    contract.set_refundable(&mut app, &owner, true).unwrap();
+    contract.request_refund(&mut app, &sender).unwrap();
+
+    // Only the donation still held came back, the withdrawn one stays in the total
+    assert_eq!(
+        app.wrap().query_all_balances(&sender).unwrap(),
+        coins(20, ATOM)
+    );
+    assert_eq!(
+        DONOR_TOTALS
+            .query(&app.wrap(), contract.addr().clone(), &sender)
+            .unwrap(),
+        Some(Uint128::new(10))
+    );
+
+    // The refunded donation can't be matched anymore
+    let err = contract
+        .donate_matching(&mut app, &sender, &sender, &coins(10, ATOM))
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::UnknownDonor {
+            donor: sender.to_string()
+        }
+    );
+}
+
+#[test]
+fn request_refund_after_round() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+    let other = Addr::unchecked("other");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, coins(20, ATOM))
+            .unwrap();
+        router
+            .bank
+            .init_balance(storage, &other, coins(30, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate_with_msg(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        &InstantiateMsg {
+            minimal_donation: coin(10, ATOM),
+            round_size: Some(3),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+    contract.donate(&mut app, &other, &coins(10, ATOM)).unwrap();
+    contract.donate(&mut app, &other, &coins(10, ATOM)).unwrap();
+    assert_eq!(contract.query_rounds_completed(&app).unwrap(), 1);
+
+    contract.donate(&mut app, &other, &coins(10, ATOM)).unwrap();
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+
+    contract.set_refundable(&mut app, &owner, true).unwrap();
+    contract.request_refund(&mut app, &sender).unwrap();
+
+    // The first donation went with the completed round, only the second one is taken back
+    let resp = contract.query_value(&app).unwrap();
+    assert_eq!(resp, ValueResp { value: 1 });
+}
+
+#[test]
+fn unauthorized_pause() {
+    let owner = Addr::unchecked("owner");
+    let member = Addr::unchecked("member");
+
+    let mut app = App::default();
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        None,
+    )
+    .unwrap();
+
+    // pausing goes through `UpdateConfig`, there is no separate message for it
+    let err = contract
+        .update_config(&mut app, &member, None, true, None)
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Unauthorized {
+            owner: owner.to_string()
+        }
+    );
+    assert!(!contract.query_flags(&app).unwrap().paused);
+
+    contract
+        .update_config(&mut app, &owner, None, true, None)
+        .unwrap();
+    assert!(contract.query_flags(&app).unwrap().paused);
+
+    let err = contract
+        .update_config(&mut app, &member, None, false, None)
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Unauthorized {
+            owner: owner.to_string()
+        }
+    );
+    assert!(contract.query_flags(&app).unwrap().paused);
+
+    contract
+        .update_config(&mut app, &owner, None, false, None)
+        .unwrap();
+    assert!(!contract.query_flags(&app).unwrap().paused);
+}
+
+#[test]
+fn flags() {
+    let owner = Addr::unchecked("owner");
+
+    let mut app = App::default();
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate_with_msg(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        &InstantiateMsg {
+            minimal_donation: coin(10, ATOM),
+            allowed_donors: Some(vec![owner.to_string()]),
+            refund_excess: true,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    let resp = contract.query_flags(&app).unwrap();
+    assert_eq!(
+        resp,
+        FlagsResp {
+            paused: false,
+            refund_excess: true,
+            accept_funds_when_free: true,
+            allowed_donors_only: true,
+            has_parent: false,
+            has_goal: false,
+            has_rounds: false,
+            has_withdraw_cut: false,
+            has_decay: false,
+            minimal_donation_bps: false,
+            has_tolerance: false,
+            has_reserve: false,
+            has_withdraw_cooldown: false,
+            has_stipend: false,
+            refundable: false,
+            has_milestones: false,
+            has_streaks: false,
+            has_cw20_policy: false,
+            has_max_forward_depth: false,
+            has_event_prefix: false,
+        }
+    );
+}
+
+#[test]
+fn flags_all_enabled() {
+    let owner = Addr::unchecked("owner");
+
+    let mut app = App::default();
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate_with_msg(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        &InstantiateMsg {
+            minimal_donation: coin(10, ATOM),
+            parent: Some(Parent {
+                addr: "parent".to_owned(),
+                donating_period: 3,
+                part: Decimal::percent(10),
+                fee: None,
+            }),
+            allowed_donors: Some(vec![owner.to_string()]),
+            event_prefix: Some("counting".to_owned()),
+            max_counter: Some(100),
+            refund_excess: true,
+            round_size: Some(10),
+            withdraw_cut: Some(("beneficiary".to_owned(), Decimal::percent(10))),
+            withdraw_cooldown: Some(60),
+            reserve: Some(coin(5, ATOM)),
+            stipend: Some(coin(1, ATOM)),
+            stipend_period: 10,
+            decay_per_block: Some(1),
+            max_forward_depth: Some(2),
+            streak_window: Some(10),
+            cw20_minimal_donation: Some(("token".to_owned(), Uint128::new(100))),
+            tolerance: Some(Uint128::new(1)),
+            milestone_size: Some(5),
+            minimal_donation_bps: true,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    contract.set_refundable(&mut app, &owner, true).unwrap();
+    contract
+        .update_config(&mut app, &owner, None, Some(true), None)
+        .unwrap();
+
+    let resp = contract.query_flags(&app).unwrap();
+    assert_eq!(
+        resp,
+        FlagsResp {
+            paused: true,
+            refund_excess: true,
+            accept_funds_when_free: true,
+            allowed_donors_only: true,
+            has_parent: true,
+            has_goal: true,
+            has_rounds: true,
+            has_withdraw_cut: true,
+            has_decay: true,
+            minimal_donation_bps: true,
+            has_tolerance: true,
+            has_reserve: true,
+            has_withdraw_cooldown: true,
+            has_stipend: true,
+            refundable: true,
+            has_milestones: true,
+            has_streaks: true,
+            has_cw20_policy: true,
+            has_max_forward_depth: true,
+            has_event_prefix: true,
+        }
+    );
+}
+
+#[cfg(feature = "debug")]
+#[test]
+fn debug() {
+    let owner = Addr::unchecked("owner");
+
+    let mut app = App::default();
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        7,
+        coin(10, ATOM),
+        None,
+    )
+    .unwrap();
+
+    let resp = contract.query_debug(&app).unwrap();
+    let state = resp
+        .entries
+        .iter()
+        .find(|entry| entry.key == "state")
+        .unwrap();
+    assert!(state.value.contains(r#""counter":7"#));
+    assert!(state.value.contains(r#""owner":"owner""#));
+}
+
+#[test]
+fn fund() {
+    let owner = Addr::unchecked("owner");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &owner, coins(10, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        None,
+    )
+    .unwrap();
+
+    contract.fund(&mut app, &owner, &coins(10, ATOM)).unwrap();
+
+    let resp = contract.query_value(&app).unwrap();
+    assert_eq!(resp, ValueResp { value: 0 });
+
+    assert_eq!(
+        app.wrap().query_all_balances(contract.addr()).unwrap(),
+        coins(10, ATOM)
+    );
+    assert!(contract
+        .query_top_donors(&app, None)
+        .unwrap()
+        .donors
+        .is_empty());
+}
+
+#[test]
+fn donate_cw20() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+    let token = Addr::unchecked("token");
+    let other_token = Addr::unchecked("other_token");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, coins(10, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate_with_msg(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        &InstantiateMsg {
+            minimal_donation: coin(10, ATOM),
+            cw20_minimal_donation: Some((token.to_string(), Uint128::new(100))),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    assert_eq!(
+        contract.query_cw20_policy(&app).unwrap(),
+        Some(Cw20PolicyResp {
+            token: token.clone(),
+            minimal_donation: Uint128::new(100)
+        })
+    );
+
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+    contract
+        .receive_cw20(&mut app, &token, &sender, 100)
+        .unwrap();
+
+    let resp = contract.query_value(&app).unwrap();
+    assert_eq!(resp, ValueResp { value: 2 });
+
+    // under the cw20 minimum
+    contract
+        .receive_cw20(&mut app, &token, &sender, 50)
+        .unwrap();
+
+    let resp = contract.query_value(&app).unwrap();
+    assert_eq!(resp, ValueResp { value: 2 });
+
+    let err = contract
+        .receive_cw20(&mut app, &other_token, &sender, 100)
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Cw20NotAccepted {
+            token: other_token.to_string()
+        }
+    );
+}
+
+#[test]
+fn cw20_receipts() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+    let token = Addr::unchecked("token");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, coins(10, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate_with_msg(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        &InstantiateMsg {
+            minimal_donation: coin(10, ATOM),
+            cw20_minimal_donation: Some((token.to_string(), Uint128::new(100))),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+
+    let resp = app
+        .execute_contract(
+            token.clone(),
+            contract.addr().clone(),
+            &ExecMsg::Receive(Cw20ReceiveMsg {
+                sender: sender.to_string(),
+                amount: Uint128::new(100),
+                msg: Binary::default(),
+            }),
+            &[],
+        )
+        .unwrap();
+    resp.assert_event(
+        &Event::new("wasm-receipt")
+            .add_attribute("receipt_id", "2")
+            .add_attribute("donor", sender.as_str())
+            .add_attribute("amount", coin(100, token.as_str()).to_string()),
+    );
+
+    // under the cw20 minimum, recorded without a receipt
+    contract
+        .receive_cw20(&mut app, &token, &sender, 50)
+        .unwrap();
+
+    assert_eq!(contract.query_receipt_count(&app).unwrap(), 2);
+    assert_eq!(
+        contract.query_recent_donations(&app, 2).unwrap(),
+        vec![
+            DonationRecord {
+                donor: sender.clone(),
+                amount: coins(50, token.as_str()),
+                height: app.block_info().height,
+                counted: false,
+            },
+            DonationRecord {
+                donor: sender.clone(),
+                amount: coins(100, token.as_str()),
+                height: app.block_info().height,
+                counted: true,
+            },
+        ]
+    );
+}
+
+#[test]
+fn cw20_counted_in_total_counter() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+    let token = Addr::unchecked("token");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, coins(20, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate_with_msg(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        &InstantiateMsg {
+            minimal_donation: coin(10, ATOM),
+            cw20_minimal_donation: Some((token.to_string(), Uint128::new(100))),
+            max_counter: Some(2),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+    contract
+        .receive_cw20(&mut app, &token, &sender, 100)
+        .unwrap();
+
+    let resp = contract.query_goal_progress(&app).unwrap();
+    assert_eq!(
+        resp,
+        GoalProgressResp {
+            current: 2,
+            goal: 2,
+            fraction: Decimal::one(),
+        }
+    );
+
+    // The cw20 donation reached the goal, so native ones are sent back
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+    assert_eq!(
+        app.wrap().query_all_balances(&sender).unwrap(),
+        coins(10, ATOM)
+    );
+
+    let resp = app
+        .execute_contract(
+            owner.clone(),
+            contract.addr().clone(),
+            &ExecMsg::RecomputeStats {},
+            &[],
+        )
+        .unwrap();
+    let wasm = resp.events.iter().find(|ev| ev.ty == "wasm").unwrap();
+    assert!(wasm.attributes.iter().all(|attr| attr.key != "drift"));
+
+    contract.finalize_campaign(&mut app, &sender).unwrap();
+
+    contract.reset(&mut app, &owner, 0).unwrap();
+
+    let resp = contract.query_value(&app).unwrap();
+    assert_eq!(resp, ValueResp { value: 0 });
+}
+
+#[test]
+fn cw20_counted_in_milestones_and_rounds() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+    let token = Addr::unchecked("token");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, coins(20, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate_with_msg(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        &InstantiateMsg {
+            minimal_donation: coin(10, ATOM),
+            cw20_minimal_donation: Some((token.to_string(), Uint128::new(100))),
+            milestone_size: Some(2),
+            round_size: Some(2),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    contract
+        .receive_cw20(&mut app, &token, &sender, 100)
+        .unwrap();
+
+    let height = app.block_info().height;
+    assert_eq!(
+        contract.query_counter_at_height(&app, height).unwrap(),
+        ValueResp { value: 1 }
+    );
+    assert_eq!(contract.query_until_next_milestone(&app).unwrap(), Some(1));
+
+    let resp = app
+        .execute_contract(
+            sender.clone(),
+            contract.addr().clone(),
+            &ExecMsg::Donate {
+                forward_depth: None,
+                idempotency_key: None,
+            },
+            &coins(10, ATOM),
+        )
+        .unwrap();
+    let wasm = resp.events.iter().find(|ev| ev.ty == "wasm").unwrap();
+    assert!(wasm
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "milestone" && attr.value == "2"));
+
+    // The round took the cw20 donation along, so nothing of it is left in the value
+    assert_eq!(contract.query_rounds_completed(&app).unwrap(), 1);
+    assert_eq!(contract.query_value(&app).unwrap(), ValueResp { value: 0 });
+    assert_eq!(
+        contract.query_counter_at_height(&app, height).unwrap(),
+        ValueResp { value: 0 }
+    );
+}
+
+#[test]
+fn last_modified() {
+    let admin = Addr::unchecked("admin");
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, coins(10, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        &admin,
+        None,
+        coin(10, ATOM),
+        None,
+    )
+    .unwrap();
+
+    app.update_block(|block| block.height += 3);
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+    let donation_height = app.block_info().height;
+
+    app.update_block(|block| block.height += 10);
+    contract.query_value(&app).unwrap();
+
+    assert_eq!(contract.query_last_modified(&app).unwrap(), donation_height);
+
+    // Migrations may rewrite any of the state as well
+    app.update_block(|block| block.height += 5);
+    let contract =
+        CountingContract::migrate(&mut app, contract.into(), code_id, &admin, None).unwrap();
+
+    assert_eq!(
+        contract.query_last_modified(&app).unwrap(),
+        app.block_info().height
+    );
+}
+
+#[test]
+fn donation_streak() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, coins(30, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate_with_msg(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        &InstantiateMsg {
+            minimal_donation: coin(10, ATOM),
+            streak_window: Some(10),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    assert_eq!(contract.query_streak(&app, sender.as_str()).unwrap(), 0);
+
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+    assert_eq!(contract.query_streak(&app, sender.as_str()).unwrap(), 1);
+
+    app.update_block(|block| block.height += 10);
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+    assert_eq!(contract.query_streak(&app, sender.as_str()).unwrap(), 2);
+
+    app.update_block(|block| block.height += 11);
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+    assert_eq!(contract.query_streak(&app, sender.as_str()).unwrap(), 1);
+}
+
+#[test]
+fn withdraw_as() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, vec![coin(10, ATOM), coin(5, JUNO)])
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        None,
+    )
+    .unwrap();
+
+    contract
+        .fund(&mut app, &sender, &[coin(10, ATOM), coin(5, JUNO)])
+        .unwrap();
+
+    let err = contract.withdraw_as(&mut app, &owner, ATOM).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::MixedDenoms {
+            denom: ATOM.to_owned()
+        }
+    );
+
+    contract
+        .withdraw_to(&mut app, &owner, &owner, coins(5, JUNO))
+        .unwrap();
+
+    let resp = contract.withdraw_as(&mut app, &owner, ATOM).unwrap();
+    assert_eq!(
+        resp,
+        WithdrawResp {
+            sent: coins(10, ATOM)
+        }
+    );
+
+    assert_eq!(
+        app.wrap().query_all_balances(&owner).unwrap(),
+        vec![coin(10, ATOM), coin(5, JUNO)]
+    );
+    assert_eq!(
+        app.wrap().query_all_balances(contract.addr()).unwrap(),
+        vec![]
+    );
+}
+
+#[test]
+fn scheduled_donation() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+    let cranker = Addr::unchecked("cranker");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, coins(20, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        None,
+    )
+    .unwrap();
+
+    let err = contract
+        .execute_scheduled(&mut app, &cranker, &sender)
+        .unwrap_err();
+    assert_eq!(err, ContractError::NoScheduledDonation {});
+
+    let err = contract
+        .schedule_donation(&mut app, &sender, 0, coin(10, ATOM), &coins(20, ATOM))
+        .unwrap_err();
+    assert_eq!(err, ContractError::InvalidPeriod {});
+
+    contract
+        .schedule_donation(&mut app, &sender, 5, coin(10, ATOM), &coins(20, ATOM))
+        .unwrap();
+
+    let height = app.block_info().height;
+    let err = contract
+        .execute_scheduled(&mut app, &cranker, &sender)
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::ScheduledDonationNotDue {
+            available_at: height + 5
+        }
+    );
+
+    app.update_block(|block| block.height += 5);
+    contract
+        .execute_scheduled(&mut app, &cranker, &sender)
+        .unwrap();
+
+    let resp = contract.query_value(&app).unwrap();
+    assert_eq!(resp, ValueResp { value: 1 });
+
+    app.update_block(|block| block.height += 5);
+    contract
+        .execute_scheduled(&mut app, &cranker, &sender)
+        .unwrap();
+
+    let resp = contract.query_value(&app).unwrap();
+    assert_eq!(resp, ValueResp { value: 2 });
+
+    app.update_block(|block| block.height += 5);
+    let err = contract
+        .execute_scheduled(&mut app, &cranker, &sender)
+        .unwrap_err();
+    assert_eq!(err, ContractError::InsufficientDeposit {});
+
+    assert_eq!(
+        app.wrap().query_all_balances(contract.addr()).unwrap(),
+        coins(20, ATOM)
+    );
+}
+
+#[test]
+fn scheduled_donation_escrow() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+    let cranker = Addr::unchecked("cranker");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, coins(20, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        None,
+    )
+    .unwrap();
+
+    contract
+        .schedule_donation(&mut app, &sender, 5, coin(10, ATOM), &coins(20, ATOM))
+        .unwrap();
+
+    // The deposit is not withdrawable until it is donated
+    contract.withdraw(&mut app, &owner).unwrap();
+    assert_eq!(app.wrap().query_all_balances(&owner).unwrap(), vec![]);
+
+    app.update_block(|block| block.height += 5);
+    contract
+        .execute_scheduled(&mut app, &cranker, &sender)
+        .unwrap();
+
+    contract.withdraw(&mut app, &owner).unwrap();
+    assert_eq!(
+        app.wrap().query_all_balances(&owner).unwrap(),
+        coins(10, ATOM)
+    );
+
+    contract
+        .cancel_scheduled_donation(&mut app, &sender)
+        .unwrap();
+    assert_eq!(
+        app.wrap().query_all_balances(&sender).unwrap(),
+        coins(10, ATOM)
+    );
+    assert_eq!(
+        app.wrap().query_all_balances(contract.addr()).unwrap(),
+        vec![]
+    );
+
+    let err = contract
+        .cancel_scheduled_donation(&mut app, &sender)
+        .unwrap_err();
+    assert_eq!(err, ContractError::NoScheduledDonation {});
+
+    let resp = contract.query_value(&app).unwrap();
+    assert_eq!(resp, ValueResp { value: 1 });
+}
+
+#[test]
+fn reconcile() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, coins(35, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        None,
+    )
+    .unwrap();
+
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+    app.send_tokens(sender.clone(), contract.addr().clone(), &coins(25, ATOM))
+        .unwrap();
+
+    let err = contract.reconcile(&mut app, &sender).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Unauthorized {
+            owner: owner.to_string()
+        }
+    );
+
+    contract.reconcile(&mut app, &owner).unwrap();
+
+    let resp = contract.query_value(&app).unwrap();
+    assert_eq!(resp, ValueResp { value: 3 });
+
+    // Only the two counted donations are tracked, the remaining 5 atom are not
+    let total = TOTAL_DONATED
+        .query(&app.wrap(), contract.addr().clone(), ATOM)
+        .unwrap();
+    assert_eq!(total, Some(Uint128::new(30)));
+
+    // nothing is left to adopt
+    contract.reconcile(&mut app, &owner).unwrap();
+
+    let resp = contract.query_value(&app).unwrap();
+    assert_eq!(resp, ValueResp { value: 3 });
+}
+
+#[test]
+fn reconcile_after_withdraw() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, coins(30, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        None,
+    )
+    .unwrap();
+
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+    contract.withdraw(&mut app, &owner).unwrap();
+    app.send_tokens(sender.clone(), contract.addr().clone(), &coins(20, ATOM))
+        .unwrap();
+
+    contract.reconcile(&mut app, &owner).unwrap();
+
+    let resp = contract.query_value(&app).unwrap();
+    assert_eq!(resp, ValueResp { value: 3 });
+
+    // Adopted funds are tracked, so the accounting adds up again
+    assert_eq!(
+        contract.query_accounting(&app).unwrap(),
+        AccountingResp {
+            donated: coins(30, ATOM),
+            withdrawn: coins(10, ATOM),
+            held: coins(20, ATOM),
+        }
+    );
+}
+
+#[test]
+fn reconcile_skips_escrow_and_decays() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, coins(40, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate_with_msg(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        &InstantiateMsg {
+            counter: 5,
+            minimal_donation: coin(10, ATOM),
+            decay_per_block: Some(1),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    contract
+        .schedule_donation(&mut app, &sender, 5, coin(10, ATOM), &coins(30, ATOM))
+        .unwrap();
+    app.send_tokens(sender.clone(), contract.addr().clone(), &coins(10, ATOM))
+        .unwrap();
+
+    app.update_block(|block| block.height += 3);
+    contract.reconcile(&mut app, &owner).unwrap();
+
+    // 5 decayed by 3 blocks, plus the single donation sent outside of the escrow
+    let resp = contract.query_value(&app).unwrap();
+    assert_eq!(resp, ValueResp { value: 3 });
+
+    // The decay starts over from the reconcile
+    app.update_block(|block| block.height += 1);
+    let resp = contract.query_value(&app).unwrap();
+    assert_eq!(resp, ValueResp { value: 2 });
+}
+
+#[test]
+fn vanity_counter() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, coins(10, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        None,
+    )
+    .unwrap();
+
+    contract.increment_vanity(&mut app, &owner).unwrap();
+    contract.increment_vanity(&mut app, &owner).unwrap();
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+
+    let resp = contract.query_vanity(&app).unwrap();
+    assert_eq!(resp, ValueResp { value: 2 });
+
+    let resp = contract.query_value(&app).unwrap();
+    assert_eq!(resp, ValueResp { value: 1 });
+
+    let err = contract.increment_vanity(&mut app, &sender).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Unauthorized {
+            owner: owner.to_string()
+        }
+    );
+}
+
+#[test]
+fn allowed_actions() {
+    let owner = Addr::unchecked("owner");
+    let member = Addr::unchecked("member");
+
+    let mut app = App::default();
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        None,
+    )
+    .unwrap();
+
+    let resp = contract.query_allowed_actions(&app, &owner).unwrap();
+    assert!(resp.contains(&"withdraw".to_owned()));
+    assert!(resp.contains(&"donate".to_owned()));
+    assert!(!resp.contains(&"remove_parent".to_owned()));
+
+    assert!(resp.contains(&"handover".to_owned()));
+    assert!(resp.contains(&"withdraw_to_many".to_owned()));
+    assert!(!resp.contains(&"tip_last_donor".to_owned()));
+
+    let resp = contract.query_allowed_actions(&app, &member).unwrap();
+    assert_eq!(
+        resp,
+        vec!["donate", "donate_matching", "schedule_donation", "fund"]
+    );
+
+    contract.set_refundable(&mut app, &owner, true).unwrap();
+    contract.block_donor(&mut app, &owner, &member).unwrap();
+
+    // Refunds are open, but the member has nothing to be refunded
+    let resp = contract.query_allowed_actions(&app, &member).unwrap();
+    assert_eq!(resp, vec!["fund"]);
+}
+
+#[test]
+fn allowed_actions_by_role() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+    let cranker = Addr::unchecked("cranker");
+    let token = Addr::unchecked("token");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, coins(30, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate_with_msg(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        &InstantiateMsg {
+            minimal_donation: coin(10, ATOM),
+            cw20_minimal_donation: Some((token.to_string(), Uint128::new(100))),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+    contract
+        .schedule_donation(&mut app, &sender, 1, coin(10, ATOM), &coins(20, ATOM))
+        .unwrap();
+    contract.set_refundable(&mut app, &owner, true).unwrap();
+
+    let resp = contract.query_allowed_actions(&app, &sender).unwrap();
+    assert!(resp.contains(&"request_refund".to_owned()));
+    assert!(resp.contains(&"cancel_scheduled_donation".to_owned()));
+    assert!(!resp.contains(&"execute_scheduled".to_owned()));
+
+    app.update_block(|block| block.height += 1);
+
+    let resp = contract.query_allowed_actions(&app, &cranker).unwrap();
+    assert!(resp.contains(&"execute_scheduled".to_owned()));
+    assert!(!resp.contains(&"request_refund".to_owned()));
+    assert!(!resp.contains(&"receive".to_owned()));
+
+    let resp = contract.query_allowed_actions(&app, &token).unwrap();
+    assert!(resp.contains(&"receive".to_owned()));
+}
+
+#[test]
+fn state_size() {
+    let owner = Addr::unchecked("owner");
+
+    let mut app = App::default();
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        None,
+    )
+    .unwrap();
+
+    let size = contract.query_state_size(&app).unwrap();
+    assert!(size > 0);
+
+    // `null` takes four bytes, so the period has to be longer than that to grow the state
+    let child = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Child contract",
+        None,
+        None,
+        coin(10, ATOM),
+        Parent {
+            addr: contract.addr().to_string(),
+            donating_period: 10000,
+            part: Decimal::percent(10),
+            fee: None,
+        },
+    )
+    .unwrap();
+
+    let parent_size = child.query_state_size(&app).unwrap();
+    assert!(parent_size > size);
+}
+
+#[test]
+fn describe() {
+    let owner = Addr::unchecked("owner");
+
+    let mut app = App::default();
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        7,
+        coin(10, ATOM),
+        None,
+    )
+    .unwrap();
+
+    let resp = contract.query_describe(&app).unwrap();
+    assert!(resp.contains("owner: owner"));
+    assert!(resp.contains("counter: 7"));
+    assert!(resp.contains("parent: none"));
+}
+
+#[test]
+fn total_donated_paged() {
+    let sender = Addr::unchecked("sender");
+
+    let funds: Vec<_> = (0..15)
+        .map(|idx| coin(10, format!("denom{idx:02}")))
+        .collect();
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, funds.clone())
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &sender,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        None,
+    )
+    .unwrap();
+
+    contract.donate(&mut app, &sender, &funds).unwrap();
+
+    // the default limit applies
+    let page = contract
+        .query_total_donated_paged(&app, None, None)
+        .unwrap();
+    assert_eq!(page, funds[..10]);
+
+    let page = contract
+        .query_total_donated_paged(&app, page.last().unwrap().denom.clone(), None)
+        .unwrap();
+    assert_eq!(page, funds[10..]);
+
+    // a limit above the maximum is clamped rather than rejected
+    let page = contract.query_total_donated_paged(&app, None, 100).unwrap();
+    assert_eq!(page, funds);
+
+    let page = contract.query_total_donated_paged(&app, None, 3).unwrap();
+    assert_eq!(page, funds[..3]);
+}
+
+#[test]
+fn donate_with_decay() {
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, coins(40, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate_with_msg(
+        &mut app,
+        code_id,
+        &sender,
+        "Counting contract",
+        None,
+        &InstantiateMsg {
+            minimal_donation: coin(10, ATOM),
+            decay_per_block: Some(1),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    for _ in 0..3 {
+        contract
+            .donate(&mut app, &sender, &coins(10, ATOM))
+            .unwrap();
+    }
+
+    let resp = contract.query_value(&app).unwrap();
+    assert_eq!(resp, ValueResp { value: 3 });
+
+    app.update_block(|block| block.height += 2);
+    let resp = contract.query_value(&app).unwrap();
+    assert_eq!(resp, ValueResp { value: 1 });
+
+    // the decay is applied before the donation counts
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+    let resp = contract.query_value(&app).unwrap();
+    assert_eq!(resp, ValueResp { value: 2 });
+
+    app.update_block(|block| block.height += 10);
+    let resp = contract.query_value(&app).unwrap();
+    assert_eq!(resp, ValueResp { value: 0 });
+}
+
+#[test]
+fn projected_at_height() {
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, coins(50, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate_with_msg(
+        &mut app,
+        code_id,
+        &sender,
+        "Counting contract",
+        None,
+        &InstantiateMsg {
+            minimal_donation: coin(10, ATOM),
+            decay_per_block: Some(2),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    for _ in 0..5 {
+        contract
+            .donate(&mut app, &sender, &coins(10, ATOM))
+            .unwrap();
+    }
+
+    let height = app.block_info().height;
+    let projected: Vec<u64> = (0..4)
+        .map(|blocks| {
+            contract
+                .query_projected_at_height(&app, height + blocks)
+                .unwrap()
+                .value
+        })
+        .collect();
+    assert_eq!(projected, vec![5, 3, 1, 0]);
+
+    // the projection matches what is observed once the block is reached
+    app.update_block(|block| block.height += 1);
+    let resp = contract.query_value(&app).unwrap();
+    assert_eq!(resp, ValueResp { value: 3 });
+
+    let err = contract
+        .query_projected_at_height(&app, height)
+        .unwrap_err();
+    assert!(err.to_string().contains("Invalid height"));
+}
+
+#[test]
+fn handover() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, vec![coin(30, ATOM), coin(5, JUNO)])
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        None,
+    )
+    .unwrap();
+    let successor = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract successor",
+        None,
+        None,
+        coin(10, ATOM),
+        None,
+    )
+    .unwrap();
+
+    contract
+        .donate(&mut app, &sender, &[coin(10, ATOM), coin(5, JUNO)])
+        .unwrap();
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+
+    // only a counting contract can take over
+    let err = contract.handover(&mut app, &owner, &sender).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidSuccessor {
+            successor: sender.to_string()
+        }
+    );
+
+    // the successor has to expect the handover
+    let err = contract
+        .handover(&mut app, &owner, successor.addr())
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::SuccessorNotReady {
+            successor: successor.addr().to_string()
+        }
+    );
+
+    // nobody else can import a counter
+    let err: ContractError = app
+        .execute_contract(
+            sender.clone(),
+            successor.addr().clone(),
+            &ExecMsg::ImportCounter { counter: 100 },
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(
+        err,
+        ContractError::NotPredecessor {
+            sender: sender.to_string()
+        }
+    );
+
+    successor
+        .set_predecessor(&mut app, &owner, contract.addr())
+        .unwrap();
+    assert_eq!(
+        successor.query_predecessor(&app).unwrap(),
+        Some(contract.addr().clone())
+    );
+    contract
+        .handover(&mut app, &owner, successor.addr())
+        .unwrap();
+    assert_eq!(successor.query_predecessor(&app).unwrap(), None);
+
+    let resp = successor.query_value(&app).unwrap();
+    assert_eq!(resp, ValueResp { value: 2 });
+    assert_eq!(
+        app.wrap().query_all_balances(successor.addr()).unwrap(),
+        vec![coin(20, ATOM), coin(5, JUNO)]
+    );
+    assert_eq!(
+        app.wrap().query_all_balances(contract.addr()).unwrap(),
+        vec![]
+    );
+
+    assert!(contract.query_flags(&app).unwrap().paused);
+    let err = contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap_err();
+    assert_eq!(err, ContractError::Paused {});
+
+    // the approval is used up
+    let err = contract
+        .handover(&mut app, &owner, successor.addr())
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::SuccessorNotReady {
+            successor: successor.addr().to_string()
+        }
+    );
+}
+
+#[test]
+fn finalize_campaign() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, coins(30, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate_with_msg(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        &InstantiateMsg {
+            minimal_donation: coin(10, ATOM),
+            max_counter: Some(2),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+
+    let err = contract.finalize_campaign(&mut app, &sender).unwrap_err();
+    assert_eq!(err, ContractError::GoalNotReached {});
+
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+
+    contract.finalize_campaign(&mut app, &sender).unwrap();
+
+    assert_eq!(
+        app.wrap().query_all_balances(&owner).unwrap(),
+        coins(20, ATOM)
+    );
+
+    let err = contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap_err();
+    assert_eq!(err, ContractError::Paused {});
+}
+
+#[test]
+fn instantiate_counter_over_max_counter() {
+    let owner = Addr::unchecked("owner");
+
+    let mut app = App::default();
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let err = CountingContract::instantiate_with_msg(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        &InstantiateMsg {
+            counter: 20,
+            minimal_donation: coin(10, ATOM),
+            max_counter: Some(10),
+            ..Default::default()
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::CounterTooHigh { max: 10 });
+}
+
+#[test]
+fn donate_over_max_counter() {
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, coins(30, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate_with_msg(
+        &mut app,
+        code_id,
+        &sender,
+        "Counting contract",
+        None,
+        &InstantiateMsg {
+            minimal_donation: coin(10, ATOM),
+            max_counter: Some(2),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+
+    let resp = app
+        .execute_contract(
+            sender.clone(),
+            contract.addr().clone(),
+            &ExecMsg::Donate {
+                forward_depth: None,
+                idempotency_key: None,
+            },
+            &coins(10, ATOM),
+        )
+        .unwrap();
+    resp.assert_event(&Event::new("wasm").add_attribute("goal_reached", "true"));
+
+    let resp = contract.query_value(&app).unwrap();
+    assert_eq!(resp, ValueResp { value: 2 });
+
+    assert_eq!(
+        app.wrap().query_all_balances(sender).unwrap(),
+        coins(10, ATOM)
+    );
+    assert_eq!(
+        app.wrap().query_all_balances(contract.addr()).unwrap(),
+        coins(20, ATOM)
+    );
+}
+
+#[test]
+fn goal_progress() {
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::default();
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate_with_msg(
+        &mut app,
+        code_id,
+        &sender,
+        "Counting contract",
+        None,
+        &InstantiateMsg {
+            counter: 3,
+            minimal_donation: coin(10, ATOM),
+            max_counter: Some(10),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    let resp = contract.query_goal_progress(&app).unwrap();
+    assert_eq!(
+        resp,
+        GoalProgressResp {
+            current: 3,
+            goal: 10,
+            fraction: Decimal::percent(30),
+        }
+    );
+
+    contract.reset(&mut app, &sender, 12).unwrap();
+
+    let resp = contract.query_goal_progress(&app).unwrap();
+    assert_eq!(resp.fraction, Decimal::one());
+
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &sender,
+        "Counting contract",
+        None,
+        3,
+        coin(10, ATOM),
+        None,
+    )
+    .unwrap();
+
+    contract.query_goal_progress(&app).unwrap_err();
+}
+
+#[test]
+fn top_donors_ties() {
+    let owner = Addr::unchecked("owner");
+    let donor_a = Addr::unchecked("donor_a");
+    let donor_b = Addr::unchecked("donor_b");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &donor_a, coins(20, ATOM))
+            .unwrap();
+        router
+            .bank
+            .init_balance(storage, &donor_b, coins(20, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        None,
+    )
+    .unwrap();
+
+    // donor_b donates first, donor_a still ranks first on a tie
+    contract
+        .donate(&mut app, &donor_b, &coins(10, ATOM))
+        .unwrap();
+    contract
+        .donate(&mut app, &donor_a, &coins(10, ATOM))
+        .unwrap();
+    contract
+        .donate(&mut app, &donor_a, &coins(10, ATOM))
+        .unwrap();
+    contract
+        .donate(&mut app, &donor_b, &coins(10, ATOM))
+        .unwrap();
+
+    let expected = vec![
+        DonorCount {
+            addr: donor_a.clone(),
+            count: 2,
+        },
+        DonorCount {
+            addr: donor_b.clone(),
+            count: 2,
+        },
+    ];
+
+    for _ in 0..3 {
+        let resp = contract.query_top_donors(&app, None).unwrap();
+        assert_eq!(resp.donors, expected);
+    }
+
+    let resp = contract.query_top_donors_by_value(&app, None).unwrap();
+    assert_eq!(
+        resp.donors,
+        vec![
+            DonorTotal {
+                addr: donor_a,
+                total: Uint128::new(20)
+            },
+            DonorTotal {
+                addr: donor_b,
+                total: Uint128::new(20)
+            },
+        ]
+    );
+}
+
+#[test]
+fn top_donors_by_value() {
+    let owner = Addr::unchecked("owner");
+    let donor1 = Addr::unchecked("donor1");
+    let donor2 = Addr::unchecked("donor2");
+    let donor3 = Addr::unchecked("donor3");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &donor1, coins(30, ATOM))
+            .unwrap();
+        router
+            .bank
+            .init_balance(storage, &donor2, coins(50, ATOM))
+            .unwrap();
+        router
+            .bank
+            .init_balance(storage, &donor3, coins(10, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        None,
+    )
+    .unwrap();
+
+    contract
+        .donate(&mut app, &donor1, &coins(10, ATOM))
+        .unwrap();
+    contract
+        .donate(&mut app, &donor1, &coins(20, ATOM))
+        .unwrap();
+    contract
+        .donate(&mut app, &donor2, &coins(50, ATOM))
+        .unwrap();
+    contract
+        .donate(&mut app, &donor3, &coins(10, ATOM))
+        .unwrap();
+
+    let resp = contract.query_top_donors_by_value(&app, None).unwrap();
+    assert_eq!(
+        resp.donors,
+        vec![
+            DonorTotal {
+                addr: donor2.clone(),
+                total: Uint128::new(50)
+            },
+            DonorTotal {
+                addr: donor1,
+                total: Uint128::new(30)
+            },
+            DonorTotal {
+                addr: donor3,
+                total: Uint128::new(10)
+            },
+        ]
+    );
+
+    let resp = contract.query_top_donors_by_value(&app, 1).unwrap();
+    assert_eq!(
+        resp.donors,
+        vec![DonorTotal {
+            addr: donor2,
+            total: Uint128::new(50)
+        }]
+    );
+}
+
+#[test]
+fn set_minimal_donation_grandfathered() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, vec![coin(20, ATOM), coin(10, JUNO)])
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        None,
+    )
+    .unwrap();
+
+    let err = contract
+        .set_minimal_donation(&mut app, &sender, coin(10, JUNO), 10)
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Unauthorized {
+            owner: owner.to_string()
+        }
+    );
+
+    contract
+        .set_minimal_donation(&mut app, &owner, coin(10, JUNO), 10)
+        .unwrap();
+
+    // still within the grace period
+    app.update_block(|block| block.height += 5);
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+
+    let resp = contract.query_value(&app).unwrap();
+    assert_eq!(resp, ValueResp { value: 1 });
+
+    app.update_block(|block| block.height += 6);
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+
+    let resp = contract.query_value(&app).unwrap();
+    assert_eq!(resp, ValueResp { value: 1 });
+
+    contract
+        .donate(&mut app, &sender, &coins(10, JUNO))
+        .unwrap();
+
+    let resp = contract.query_value(&app).unwrap();
+    assert_eq!(resp, ValueResp { value: 2 });
+}
+
+#[test]
+fn init_params() {
+    let owner = Addr::unchecked("owner");
+
+    let mut app = App::default();
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let msg = InstantiateMsg {
+        counter: 3,
+        minimal_donation: coin(10, ATOM),
+        max_counter: Some(100),
+        refund_excess: true,
+        ..Default::default()
+    };
+    let contract = CountingContract::instantiate_with_msg(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        &msg,
+    )
+    .unwrap();
+
+    contract
+        .set_minimal_donation(&mut app, &owner, coin(5, JUNO), None)
+        .unwrap();
+
+    assert_eq!(contract.query_init_params(&app).unwrap(), Some(msg));
+}
+
+#[test]
+fn set_minimal_donation_reports_stale_denom_balance() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, coins(10, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        None,
+    )
+    .unwrap();
+
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+
+    let resp = app
+        .execute_contract(
+            owner.clone(),
+            contract.addr().clone(),
+            &ExecMsg::SetMinimalDonation {
+                minimal_donation: coin(10, JUNO),
+                grace_period: None,
+            },
+            &[],
+        )
+        .unwrap();
+    resp.assert_event(
+        &Event::new("wasm").add_attribute("stale_denom_balance", coin(10, ATOM).to_string()),
+    );
+
+    // Same denom, nothing is stale
+    let resp = app
+        .execute_contract(
+            owner,
+            contract.addr().clone(),
+            &ExecMsg::SetMinimalDonation {
+                minimal_donation: coin(20, JUNO),
+                grace_period: None,
+            },
+            &[],
+        )
+        .unwrap();
+    assert!(!resp
+        .events
+        .iter()
+        .flat_map(|event| &event.attributes)
+        .any(|attr| attr.key == "stale_denom_balance"));
+}
+
+#[test]
+fn effective_policy_grandfathered() {
+    let owner = Addr::unchecked("owner");
+
+    let mut app = App::default();
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        None,
+    )
+    .unwrap();
+
+    contract
+        .set_minimal_donation(&mut app, &owner, coin(5, JUNO), 10)
+        .unwrap();
+
+    // the last block of the grace period
+    app.update_block(|block| block.height += 10);
+    let resp = contract.query_effective_policy(&app).unwrap();
+    assert_eq!(
+        resp,
+        EffectivePolicyResp {
+            accepted: vec![coin(5, JUNO), coin(5, ATOM)]
+        }
+    );
+
+    app.update_block(|block| block.height += 1);
+    let resp = contract.query_effective_policy(&app).unwrap();
+    assert_eq!(
+        resp,
+        EffectivePolicyResp {
+            accepted: vec![coin(5, JUNO)]
+        }
+    );
+}
+
+#[test]
+fn donate_expecting_no_funds() {
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::default();
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &sender,
+        "Counting contract",
+        None,
+        None,
+        coin(0, ATOM),
+        None,
+    )
+    .unwrap();
+
+    // execute donate
+    contract.donate(&mut app, &sender, &[]).unwrap();
+
+    let resp = contract.query_value(&app).unwrap();
+
+    assert_eq!(resp.value, 1);
+}
+
+#[test]
+fn reset() {
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::default();
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &sender,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        None,
+    )
+    .unwrap();
+
+    // execute reset
+    contract.reset(&mut app, &sender, 10).unwrap();
+
+    let resp = contract.query_value(&app).unwrap();
+
+    assert_eq!(resp.value, 10);
+}
+
+#[test]
+fn reset_with_funds() {
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, coins(10, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &sender,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        None,
+    )
+    .unwrap();
+
+    let err: ContractError = app
+        .execute_contract(
+            sender.clone(),
+            contract.addr().clone(),
+            &ExecMsg::Reset { counter: 10 },
+            &coins(10, ATOM),
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::NonPayable {});
+
+    let resp = contract.query_value(&app).unwrap();
+    assert_eq!(resp.value, 0);
+
+    assert_eq!(
+        app.wrap().query_all_balances(sender).unwrap(),
+        coins(10, ATOM)
+    );
+    assert_eq!(
+        app.wrap().query_all_balances(contract.addr()).unwrap(),
+        vec![]
+    );
+}
+
+#[test]
+fn withdraw() {
+    let sender = Addr::unchecked("sender");
+    let owner = Addr::unchecked("owner");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, coins(10, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        None,
+    )
+    .unwrap();
+
+    // execute donate (sender)
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+
+    // execute donate (owner)
+    contract.donate(&mut app, &sender, &[]).unwrap();
+
+    // execute withdraw
+    let resp = contract.withdraw(&mut app, &owner).unwrap();
+    assert_eq!(
+        resp,
+        WithdrawResp {
+            sent: coins(10, ATOM)
+        }
+    );
+
+    assert_eq!(
+        app.wrap().query_all_balances(owner).unwrap(),
+        coins(10, ATOM)
+    );
+
+    assert_eq!(app.wrap().query_all_balances(sender).unwrap(), vec![]);
+    assert_eq!(
+        app.wrap()
+            .query_all_balances(contract.addr().clone())
+            .unwrap(),
+        vec![]
+    );
+}
+
+#[test]
+fn withdraw_with_cut() {
+    let sender = Addr::unchecked("sender");
+    let owner = Addr::unchecked("owner");
+    let beneficiary = Addr::unchecked("beneficiary");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, coins(100, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate_with_msg(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        &InstantiateMsg {
+            minimal_donation: coin(10, ATOM),
+            withdraw_cut: Some((beneficiary.to_string(), Decimal::percent(10))),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    contract
+        .donate(&mut app, &sender, &coins(100, ATOM))
+        .unwrap();
+
+    let resp = contract.withdraw(&mut app, &owner).unwrap();
+    assert_eq!(
+        resp,
+        WithdrawResp {
+            sent: coins(90, ATOM)
+        }
+    );
+
+    assert_eq!(
+        app.wrap().query_all_balances(&owner).unwrap(),
+        coins(90, ATOM)
+    );
+    assert_eq!(
+        app.wrap().query_all_balances(&beneficiary).unwrap(),
+        coins(10, ATOM)
+    );
+    assert_eq!(
+        app.wrap().query_all_balances(contract.addr()).unwrap(),
+        vec![]
+    );
+
+    let err = CountingContract::instantiate_with_msg(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        &InstantiateMsg {
+            minimal_donation: coin(10, ATOM),
+            withdraw_cut: Some((beneficiary.to_string(), Decimal::percent(110))),
+            ..Default::default()
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidWithdrawCut {});
+}
+
+#[test]
+fn withdraw_cooldown() {
+    let sender = Addr::unchecked("sender");
+    let owner = Addr::unchecked("owner");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, coins(20, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate_with_msg(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        &InstantiateMsg {
+            minimal_donation: coin(10, ATOM),
+            withdraw_cooldown: Some(100),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+    contract.withdraw(&mut app, &owner).unwrap();
+
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+
+    contract.advance_seconds(&mut app, 99);
+    let err = contract.withdraw(&mut app, &owner).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::WithdrawCooldown {
+            available_at: app.block_info().time.seconds() + 1
+        }
+    );
+
+    contract.advance_seconds(&mut app, 1);
+    contract.withdraw(&mut app, &owner).unwrap();
+
+    assert_eq!(
+        app.wrap().query_all_balances(&owner).unwrap(),
+        coins(20, ATOM)
+    );
+}
+
+#[test]
+fn withdraw_with_reserve() {
+    let sender = Addr::unchecked("sender");
+    let owner = Addr::unchecked("owner");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, coins(100, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate_with_msg(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        &InstantiateMsg {
+            minimal_donation: coin(10, ATOM),
+            reserve: Some(coin(20, ATOM)),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    contract
+        .donate(&mut app, &sender, &coins(100, ATOM))
+        .unwrap();
+
+    let resp = contract.query_withdrawable(&app).unwrap();
+    assert_eq!(resp, coins(80, ATOM));
+
+    let resp = contract.withdraw(&mut app, &owner).unwrap();
+    assert_eq!(
+        resp,
+        WithdrawResp {
+            sent: coins(80, ATOM)
+        }
+    );
+
+    let resp = contract.query_withdrawable(&app).unwrap();
+    assert_eq!(resp, vec![]);
+
+    assert_eq!(
+        app.wrap().query_all_balances(&owner).unwrap(),
+        coins(80, ATOM)
+    );
+    assert_eq!(
+        app.wrap().query_all_balances(contract.addr()).unwrap(),
+        coins(20, ATOM)
+    );
+}
+
+#[test]
+fn claim_stipend() {
+    let sender = Addr::unchecked("sender");
+    let owner = Addr::unchecked("owner");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, coins(100, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate_with_msg(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        &InstantiateMsg {
+            minimal_donation: coin(10, ATOM),
+            stipend: Some(coin(15, ATOM)),
+            stipend_period: 10,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    contract
+        .donate(&mut app, &sender, &coins(100, ATOM))
+        .unwrap();
+
+    contract.claim_stipend(&mut app, &owner).unwrap();
+
+    let err = contract.claim_stipend(&mut app, &owner).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::StipendNotReady {
+            available_at: app.block_info().height + 10
+        }
+    );
+
+    app.update_block(|block| block.height += 10);
+    contract.claim_stipend(&mut app, &owner).unwrap();
+
+    assert_eq!(
+        app.wrap().query_all_balances(&owner).unwrap(),
+        coins(30, ATOM)
+    );
+    assert_eq!(
+        app.wrap().query_all_balances(contract.addr()).unwrap(),
+        coins(70, ATOM)
+    );
+
+    let err = contract.claim_stipend(&mut app, &sender).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Unauthorized {
+            owner: owner.to_string()
+        }
+    );
+}
+
+#[test]
+fn withdraw_to_many_merges_payouts() {
+    let mut deps = mock_dependencies_with_balance(&[coin(10, ATOM), coin(10, JUNO)]);
+
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        InstantiateMsg {
+            minimal_donation: coin(10, ATOM),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    let resp = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecMsg::WithdrawToMany {
+            payouts: vec![
+                Payout {
+                    receiver: "receiver".to_owned(),
+                    amount: coin(5, ATOM),
+                },
+                Payout {
+                    receiver: "other".to_owned(),
+                    amount: coin(2, ATOM),
+                },
+                Payout {
+                    receiver: "receiver".to_owned(),
+                    amount: coin(3, JUNO),
+                },
+            ],
+        },
+    )
+    .unwrap();
+
+    assert_eq!(
+        resp.messages,
+        vec![
+            SubMsg::new(BankMsg::Send {
+                to_address: "receiver".to_owned(),
+                amount: vec![coin(5, ATOM), coin(3, JUNO)],
+            }),
+            SubMsg::new(BankMsg::Send {
+                to_address: "other".to_owned(),
+                amount: coins(2, ATOM),
+            }),
+        ]
+    );
+}
+
+#[test]
+fn withdraw_to_many_keeps_reserve() {
+    let mut deps = mock_dependencies_with_balance(&coins(10, ATOM));
+
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        InstantiateMsg {
+            minimal_donation: coin(10, ATOM),
+            reserve: Some(coin(4, ATOM)),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    let resp = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecMsg::WithdrawToMany {
+            payouts: vec![
+                Payout {
+                    receiver: "receiver".to_owned(),
+                    amount: coin(5, ATOM),
+                },
+                Payout {
+                    receiver: "other".to_owned(),
+                    amount: coin(5, ATOM),
+                },
+                Payout {
+                    receiver: "third".to_owned(),
+                    amount: coin(5, JUNO),
+                },
+            ],
+        },
+    )
+    .unwrap();
+
+    assert_eq!(
+        resp.messages,
+        vec![
+            SubMsg::new(BankMsg::Send {
+                to_address: "receiver".to_owned(),
+                amount: coins(5, ATOM),
+            }),
+            SubMsg::new(BankMsg::Send {
+                to_address: "other".to_owned(),
+                amount: coins(1, ATOM),
+            }),
+        ]
+    );
+}
+
+#[test]
+fn tip_last_donor() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, coins(10, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        None,
+    )
+    .unwrap();
+
+    let err = contract
+        .tip_last_donor(&mut app, &owner, coin(3, ATOM))
+        .unwrap_err();
+    assert_eq!(err, ContractError::NoLastDonor {});
+
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+
+    contract
+        .tip_last_donor(&mut app, &owner, coin(3, ATOM))
+        .unwrap();
+
+    assert_eq!(
+        app.wrap().query_all_balances(&sender).unwrap(),
+        coins(3, ATOM)
+    );
+
+    // clamped to the balance
+    contract
+        .tip_last_donor(&mut app, &owner, coin(100, ATOM))
+        .unwrap();
+
+    assert_eq!(
+        app.wrap().query_all_balances(&sender).unwrap(),
+        coins(10, ATOM)
+    );
+    assert_eq!(
+        app.wrap().query_all_balances(contract.addr()).unwrap(),
+        vec![]
+    );
+}
+
+#[test]
+fn withdraw_to() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+    let receiver = Addr::unchecked("receiver");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, coins(10, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        None,
+    )
+    .unwrap();
+
+    // execute donate (sender)
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+
+    // execute withdraw_to (owner -> receiver)
+    contract
+        .withdraw_to(&mut app, &owner, &receiver, coins(5, ATOM))
+        .unwrap();
+
+    assert_eq!(app.wrap().query_all_balances(owner).unwrap(), vec![]);
+    assert_eq!(app.wrap().query_all_balances(sender).unwrap(), vec![]);
+    assert_eq!(
+        app.wrap().query_all_balances(receiver).unwrap(),
+        coins(5, ATOM)
+    );
+    assert_eq!(
+        app.wrap()
+            .query_all_balances(contract.addr().clone())
+            .unwrap(),
+        coins(5, ATOM)
+    );
+}
+
+#[test]
+fn withdraw_to_multiple_denoms() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+    let receiver = Addr::unchecked("receiver");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(
+                storage,
+                &sender,
+                vec![coin(10, ATOM), coin(10, JUNO), coin(10, OSMO)],
+            )
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        None,
+    )
+    .unwrap();
+
+    // execute donate (sender)
+    contract
+        .donate(
+            &mut app,
+            &sender,
+            &[coin(10, ATOM), coin(10, JUNO), coin(10, OSMO)],
+        )
+        .unwrap();
+
+    // execute withdraw_to (owner -> receiver): atom under the cap, osmo over the cap, btc not held
+    contract
+        .withdraw_to(
+            &mut app,
+            &owner,
+            &receiver,
+            vec![coin(5, ATOM), coin(100, OSMO), coin(5, "btc")],
+        )
+        .unwrap();
+
+    assert_eq!(
+        app.wrap().query_all_balances(receiver).unwrap(),
+        vec![coin(5, ATOM), coin(10, OSMO)]
+    );
+    assert_eq!(
+        app.wrap().query_all_balances(contract.addr()).unwrap(),
+        vec![coin(5, ATOM), coin(10, JUNO)]
+    );
+}
+
+#[test]
+fn withdraw_destination() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+    let treasury = Addr::unchecked("treasury");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, coins(10, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        None,
+    )
+    .unwrap();
+
+    assert_eq!(contract.query_withdraw_destination(&app).unwrap(), owner);
+
+    let err = contract
+        .set_withdraw_destination(&mut app, &sender, &sender)
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Unauthorized {
+            owner: owner.to_string()
+        }
+    );
+
+    contract
+        .set_withdraw_destination(&mut app, &owner, &treasury)
+        .unwrap();
+    assert_eq!(contract.query_withdraw_destination(&app).unwrap(), treasury);
+
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+    contract.withdraw(&mut app, &owner).unwrap();
+
+    assert_eq!(
+        app.wrap().query_all_balances(&treasury).unwrap(),
+        coins(10, ATOM)
+    );
+    assert_eq!(app.wrap().query_all_balances(&owner).unwrap(), vec![]);
+}
+
+#[test]
+fn withdraw_to_whole_balance() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+    let receiver = Addr::unchecked("receiver");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, vec![coin(10, ATOM), coin(5, JUNO)])
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        None,
+    )
+    .unwrap();
+
+    contract
+        .donate(&mut app, &sender, &[coin(10, ATOM), coin(5, JUNO)])
+        .unwrap();
+
+    let resp = contract
+        .withdraw_to(&mut app, &owner, &receiver, vec![])
+        .unwrap();
+
+    assert_eq!(
+        resp,
+        WithdrawResp {
+            sent: vec![coin(10, ATOM), coin(5, JUNO)]
+        }
+    );
+    assert_eq!(
+        app.wrap().query_all_balances(receiver).unwrap(),
+        vec![coin(10, ATOM), coin(5, JUNO)]
+    );
+    assert_eq!(app.wrap().query_all_balances(owner).unwrap(), vec![]);
+    assert_eq!(
+        app.wrap().query_all_balances(contract.addr()).unwrap(),
+        vec![]
+    );
+}
+
+#[test]
+fn withdraw_to_skips_absent_denoms() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+    let receiver = Addr::unchecked("receiver");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, coins(10, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        None,
+    )
+    .unwrap();
+
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+
+    // juno is requested, but the contract holds none of it
+    let resp = contract
+        .withdraw_to(
+            &mut app,
+            &owner,
+            &receiver,
+            vec![coin(5, ATOM), coin(5, JUNO)],
+        )
+        .unwrap();
+
+    assert_eq!(
+        resp,
+        WithdrawResp {
+            sent: coins(5, ATOM)
+        }
+    );
+    assert_eq!(
+        app.wrap().query_all_balances(receiver).unwrap(),
+        coins(5, ATOM)
+    );
+    assert_eq!(
+        app.wrap().query_all_balances(contract.addr()).unwrap(),
+        coins(5, ATOM)
+    );
+}
+
+#[test]
+fn total_withdrawn() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+    let receiver = Addr::unchecked("receiver");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, vec![coin(10, ATOM), coin(10, JUNO)])
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        None,
+    )
+    .unwrap();
+
+    assert_eq!(contract.query_total_withdrawn(&app).unwrap(), vec![]);
+
+    contract
+        .donate(&mut app, &sender, &[coin(10, ATOM), coin(10, JUNO)])
+        .unwrap();
+
+    contract
+        .withdraw_to(&mut app, &owner, &receiver, coins(4, ATOM))
+        .unwrap();
+
+    assert_eq!(
+        contract.query_total_withdrawn(&app).unwrap(),
+        coins(4, ATOM)
+    );
+
+    contract.withdraw(&mut app, &owner).unwrap();
+
+    assert_eq!(
+        app.wrap().query_all_balances(contract.addr()).unwrap(),
+        vec![]
+    );
+    assert_eq!(
+        contract.query_total_withdrawn(&app).unwrap(),
+        vec![coin(10, ATOM), coin(10, JUNO)]
+    );
+    assert_eq!(
+        contract.query_total_withdrawn(&app).unwrap(),
+        contract
+            .query_total_donated_paged(&app, None, None)
+            .unwrap()
+    );
+}
+
+#[test]
+fn accounting() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+    let receiver = Addr::unchecked("receiver");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, vec![coin(30, ATOM), coin(10, JUNO)])
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        None,
+    )
+    .unwrap();
+
+    contract
+        .donate(&mut app, &sender, &[coin(10, ATOM), coin(10, JUNO)])
+        .unwrap();
+    contract
+        .withdraw_to(&mut app, &owner, &receiver, coins(5, JUNO))
+        .unwrap();
+    contract
+        .donate(&mut app, &sender, &coins(20, ATOM))
+        .unwrap();
+    contract
+        .withdraw_to(&mut app, &owner, &receiver, coins(12, ATOM))
+        .unwrap();
+
+    let resp = contract.query_accounting(&app).unwrap();
+    assert_eq!(
+        resp,
+        AccountingResp {
+            donated: vec![coin(30, ATOM), coin(10, JUNO)],
+            withdrawn: vec![coin(12, ATOM), coin(5, JUNO)],
+            held: vec![coin(18, ATOM), coin(5, JUNO)],
+        }
+    );
+
+    for donated in &resp.donated {
+        let withdrawn = resp
+            .withdrawn
+            .iter()
+            .find(|coin| coin.denom == donated.denom)
+            .map(|coin| coin.amount)
+            .unwrap_or_default();
+        let held = resp
+            .held
+            .iter()
+            .find(|coin| coin.denom == donated.denom)
+            .map(|coin| coin.amount)
+            .unwrap_or_default();
+
+        assert_eq!(donated.amount - withdrawn, held);
+    }
+}
+
+#[test]
+fn donate_allowed_donors() {
+    let owner = Addr::unchecked("owner");
+    let donor = Addr::unchecked("donor");
+    let outsider = Addr::unchecked("outsider");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &donor, coins(10, ATOM))
+            .unwrap();
+        router
+            .bank
+            .init_balance(storage, &outsider, coins(10, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate_with_msg(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        &InstantiateMsg {
+            minimal_donation: coin(10, ATOM),
+            allowed_donors: Some(vec![donor.to_string()]),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    assert!(contract.query_is_allowed_donor(&app, &donor).unwrap());
+    assert!(!contract.query_is_allowed_donor(&app, &outsider).unwrap());
+
+    let err = contract
+        .donate(&mut app, &outsider, &coins(10, ATOM))
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::DonorNotAllowed {
+            donor: outsider.to_string()
+        }
+    );
+
+    let err = contract
+        .add_allowed_donor(&mut app, &outsider, &outsider)
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Unauthorized {
+            owner: owner.to_string()
+        }
+    );
+
+    contract
+        .add_allowed_donor(&mut app, &owner, &outsider)
+        .unwrap();
+    contract
+        .donate(&mut app, &outsider, &coins(10, ATOM))
+        .unwrap();
+
+    contract
+        .remove_allowed_donor(&mut app, &owner, &donor)
+        .unwrap();
+    assert!(!contract.query_is_allowed_donor(&app, &donor).unwrap());
+
+    let resp = contract.query_value(&app).unwrap();
+    assert_eq!(resp, ValueResp { value: 1 });
+
+    assert_eq!(app.wrap().query_all_balances(outsider).unwrap(), vec![]);
+    assert_eq!(
+        app.wrap().query_all_balances(donor).unwrap(),
+        coins(10, ATOM)
+    );
+    assert_eq!(
+        app.wrap().query_all_balances(contract.addr()).unwrap(),
+        coins(10, ATOM)
+    );
+}
+
+#[test]
+fn donate_blocked_donor() {
+    let owner = Addr::unchecked("owner");
+    let donor = Addr::unchecked("donor");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &donor, coins(20, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate_with_msg(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        &InstantiateMsg {
+            minimal_donation: coin(10, ATOM),
+            allowed_donors: Some(vec![donor.to_string()]),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    contract.donate(&mut app, &donor, &coins(10, ATOM)).unwrap();
+
+    let err = contract.block_donor(&mut app, &donor, &donor).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Unauthorized {
+            owner: owner.to_string()
+        }
+    );
+
+    contract.block_donor(&mut app, &owner, &donor).unwrap();
+    assert!(contract.query_is_blocked(&app, &donor).unwrap());
+    // still on the allowed donors list, but the block takes precedence
+    assert!(contract.query_is_allowed_donor(&app, &donor).unwrap());
+
+    let err = contract
+        .donate(&mut app, &donor, &coins(10, ATOM))
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::DonorBlocked {
+            donor: donor.to_string()
+        }
+    );
+
+    contract.unblock_donor(&mut app, &owner, &donor).unwrap();
+    assert!(!contract.query_is_blocked(&app, &donor).unwrap());
+
+    contract.donate(&mut app, &donor, &coins(10, ATOM)).unwrap();
+
+    let resp = contract.query_value(&app).unwrap();
+    assert_eq!(resp, ValueResp { value: 2 });
+
+    assert_eq!(app.wrap().query_all_balances(donor).unwrap(), vec![]);
+    assert_eq!(
+        app.wrap().query_all_balances(contract.addr()).unwrap(),
+        coins(20, ATOM)
+    );
+}
+
+#[test]
+fn evacuate() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, coins(20, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let old_contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        None,
+    )
+    .unwrap();
+
+    let new_contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        None,
+    )
+    .unwrap();
+
+    old_contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+    old_contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+
+    let err = old_contract
+        .evacuate(&mut app, &sender, new_contract.addr(), true)
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Unauthorized {
+            owner: owner.to_string()
+        }
+    );
+
+    old_contract
+        .evacuate(&mut app, &owner, new_contract.addr(), true)
+        .unwrap();
+
+    let resp = new_contract.query_value(&app).unwrap();
+    assert_eq!(resp, ValueResp { value: 1 });
+
+    assert_eq!(
+        app.wrap().query_all_balances(old_contract.addr()).unwrap(),
+        vec![]
+    );
+    assert_eq!(
+        app.wrap().query_all_balances(new_contract.addr()).unwrap(),
+        coins(20, ATOM)
+    );
+
+    assert_eq!(
+        old_contract.query_accounting(&app).unwrap(),
+        AccountingResp {
+            donated: coins(20, ATOM),
+            withdrawn: coins(20, ATOM),
+            held: vec![],
+        }
+    );
+}
+
+#[test]
+fn unauthorized_withdraw() {
+    let owner = Addr::unchecked("owner");
+    let member = Addr::unchecked("member");
+
+    let mut app = App::default();
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        None,
+    )
+    .unwrap();
+
+    let err = contract.withdraw(&mut app, &member).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Unauthorized {
+            owner: owner.into()
+        }
+    );
+}
+
+#[test]
+fn unauthorized_withdraw_to() {
+    let owner = Addr::unchecked("owner");
+    let member = Addr::unchecked("member");
+
+    let mut app = App::default();
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(0, ATOM),
+        None,
+    )
+    .unwrap();
+
+    let err = contract
+        .withdraw_to(&mut app, &member, &owner, None)
+        .unwrap_err();
+
+    assert_eq!(
+        err,
+        ContractError::Unauthorized {
+            owner: owner.into()
+        }
+    );
+}
+
+#[test]
+fn withdraw_to_invalid_receiver() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+    let receiver = Addr::unchecked("Invalid Receiver");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, coins(10, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        None,
+    )
+    .unwrap();
+
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+
+    let err = contract
+        .withdraw_to(&mut app, &owner, &receiver, None)
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidReceiver {
+            receiver: receiver.to_string()
+        }
+    );
+
+    assert_eq!(
+        app.wrap().query_all_balances(contract.addr()).unwrap(),
+        coins(10, ATOM)
+    );
+}
+
+#[test]
+fn unauthorized_reset() {
+    let owner = Addr::unchecked("owner");
+    let member = Addr::unchecked("member");
+
+    let mut app = App::default();
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(0, ATOM),
+        None,
+    )
+    .unwrap();
+
+    let err = contract.reset(&mut app, &member, 10).unwrap_err();
+
+    assert_eq!(
+        err,
+        ContractError::Unauthorized {
+            owner: owner.into()
+        }
+    );
+}
+
+#[test]
+fn schema_version() {
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::default();
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &sender,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        None,
+    )
+    .unwrap();
+
+    let resp = contract.query_schema_version(&app).unwrap();
+    assert_eq!(
+        resp,
+        SchemaVersionResp {
+            major: 0,
+            minor: 3,
+            patch: 1
+        }
+    );
+}
+
+#[test]
+fn migration() {
+    let admin = Addr::unchecked("admin");
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, coins(10, ATOM))
+            .unwrap()
+    });
+
+    let old_code_id = CountingContract_0_1::store_code(&mut app);
+    let new_code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract_0_1::instantiate(
+        &mut app,
+        old_code_id,
+        &owner,
+        "Counting contract",
+        &admin,
+        None,
+        coin(10, ATOM),
+    )
+    .unwrap();
+
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+
+    let contract =
+        CountingContract::migrate(&mut app, contract.into(), new_code_id, &admin, None).unwrap();
+
+    let resp = contract.query_value(&app).unwrap();
+    assert_eq!(resp, ValueResp { value: 1 });
+
+    let state = STATE.query(&app.wrap(), contract.addr().clone()).unwrap();
+    assert_eq!(
+        state,
+        State {
+            counter: 1,
+            minimal_donation: coin(10, ATOM),
+            owner,
+            donating_parent: None
+        }
+    );
+}
+
+#[test]
+fn integrity_check_after_migration() {
+    let admin = Addr::unchecked("admin");
+    let owner = Addr::unchecked("owner");
+
+    let mut app = App::default();
+
+    let old_code_id = CountingContract_0_1::store_code(&mut app);
+    let new_code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract_0_1::instantiate(
+        &mut app,
+        old_code_id,
+        &owner,
+        "Counting contract",
+        &admin,
+        None,
+        coin(10, ATOM),
+    )
+    .unwrap();
+
+    let contract =
+        CountingContract::migrate(&mut app, contract.into(), new_code_id, &admin, None).unwrap();
+
+    assert_eq!(
+        contract.query_integrity_check(&app).unwrap(),
+        IntegrityCheckResp {
+            cw2_version: env!("CARGO_PKG_VERSION").to_owned(),
+            state_loadable: true,
+        }
+    );
+}
+
+#[test]
+fn integrity_check_unloadable_state() {
+    let mut deps = mock_dependencies();
+
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        InstantiateMsg {
+            minimal_donation: coin(10, ATOM),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    // what a migration stopping halfway would leave behind
+    deps.storage.set(b"state", br#"{"counter":1}"#);
+
+    let resp = contract::query::integrity_check(deps.as_ref()).unwrap();
+    assert!(!resp.state_loadable);
+}
+
+#[test]
+fn migration_resp() {
+    let admin = Addr::unchecked("admin");
+    let owner = Addr::unchecked("owner");
+
+    let mut app = App::default();
+
+    let old_code_id = CountingContract_0_1::store_code(&mut app);
+    let new_code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract_0_1::instantiate(
+        &mut app,
+        old_code_id,
+        &owner,
+        "Counting contract",
+        &admin,
+        None,
+        coin(10, ATOM),
+    )
+    .unwrap();
+
+    let (_, resp) =
+        CountingContract::migrate_with_resp(&mut app, contract.into(), new_code_id, &admin, None)
+            .unwrap();
+    assert_eq!(
+        resp,
+        MigrateResp {
+            from: "0.1.0".to_owned(),
+            to: "0.3.1".to_owned()
+        }
+    );
+}
+
+#[test]
+fn detect_migration() {
+    let mut deps = mock_dependencies();
+
+    cw2::set_contract_version(&mut deps.storage, "counting-contract", "0.1.0").unwrap();
+    let kind = contract::detect_migration(deps.as_ref()).unwrap();
+    assert_eq!(kind, MigrationKind::FromV1);
+
+    cw2::set_contract_version(&mut deps.storage, "counting-contract", "0.2.0").unwrap();
+    let kind = contract::detect_migration(deps.as_ref()).unwrap();
+    assert_eq!(kind, MigrationKind::FromV2);
+
+    cw2::set_contract_version(&mut deps.storage, "counting-contract", "0.3.0").unwrap();
+    let kind = contract::detect_migration(deps.as_ref()).unwrap();
+    assert_eq!(kind, MigrationKind::FromV3);
+
+    cw2::set_contract_version(&mut deps.storage, "counting-contract", "0.3.1").unwrap();
+    let kind = contract::detect_migration(deps.as_ref()).unwrap();
+    assert_eq!(kind, MigrationKind::Current);
+
+    cw2::set_contract_version(&mut deps.storage, "counting-contract", "0.0.1").unwrap();
+    let kind = contract::detect_migration(deps.as_ref()).unwrap();
+    assert_eq!(kind, MigrationKind::Unknown);
+
+    cw2::set_contract_version(&mut deps.storage, "other-contract", "0.2.0").unwrap();
+    let err = contract::detect_migration(deps.as_ref()).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidContract {
+            contract: "other-contract".to_owned()
+        }
+    );
+}
+
+#[test]
+fn migration_kind() {
+    let owner = Addr::unchecked("owner");
+
+    let mut app = App::default();
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        None,
+    )
+    .unwrap();
+
+    let kind = contract.query_migration_kind(&app).unwrap();
+    assert_eq!(kind, MigrationKind::Current);
+}
+
+#[test]
+fn migration_steps() {
+    let admin = Addr::unchecked("admin");
+    let owner = Addr::unchecked("owner");
+
+    let mut app = App::default();
+
+    let old_code_id = CountingContract_0_1::store_code(&mut app);
+    let new_code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract_0_1::instantiate(
+        &mut app,
+        old_code_id,
+        &owner,
+        "Counting contract",
+        &admin,
+        None,
+        coin(10, ATOM),
+    )
+    .unwrap();
+
+    let resp = app
+        .migrate_contract(
+            admin,
+            contract.into(),
+            &MigrateMsg { parent: None },
+            new_code_id,
+        )
+        .unwrap();
+
+    let steps: Vec<_> = resp
+        .events
+        .iter()
+        .filter(|event| event.ty == "wasm")
+        .flat_map(|event| &event.attributes)
+        .filter(|attr| attr.key == "migrated_step")
+        .map(|attr| attr.value.as_str())
+        .collect();
+    assert_eq!(steps, vec!["0.1.0->0.2.0", "0.2.0->0.3.0", "0.3.0->0.3.1"]);
+}
+
+#[test]
+fn needs_migration() {
+    let admin = Addr::unchecked("admin");
+    let owner = Addr::unchecked("owner");
+
+    let mut app = App::default();
+
+    // Rolls the cw2 record back to 0.1.0, while still answering the current queries
+    fn stale_migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> StdResult<Response> {
+        cw2::set_contract_version(deps.storage, "counting-contract", "0.1.0")?;
+
+        Ok(Response::new())
+    }
+
+    let code_id = CountingContract::store_code(&mut app);
+    let stale_code_id = app.store_code(Box::new(
+        ContractWrapper::new(execute, instantiate, query).with_migrate(stale_migrate),
+    ));
+
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        &admin,
+        None,
+        coin(10, ATOM),
+        None,
+    )
+    .unwrap();
+
+    assert!(!contract.query_needs_migration(&app).unwrap());
+
+    app.migrate_contract(
+        admin,
+        contract.addr().clone(),
+        &MigrateMsg { parent: None },
+        stale_code_id,
+    )
+    .unwrap();
+
+    assert!(contract.query_needs_migration(&app).unwrap());
+}
+
+#[test]
+fn migration_preserves_funds() {
+    let admin = Addr::unchecked("admin");
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, coins(10, ATOM))
+            .unwrap()
+    });
+
+    let old_code_id = CountingContract_0_1::store_code(&mut app);
+    let new_code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract_0_1::instantiate(
+        &mut app,
+        old_code_id,
+        &owner,
+        "Counting contract",
+        &admin,
+        None,
+        coin(10, ATOM),
+    )
+    .unwrap();
+
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+
+    let balance = app.wrap().query_all_balances(contract.addr()).unwrap();
+    assert_eq!(balance, coins(10, ATOM));
+
+    let contract =
+        CountingContract::migrate(&mut app, contract.into(), new_code_id, &admin, None).unwrap();
+
+    assert_eq!(
+        app.wrap().query_all_balances(contract.addr()).unwrap(),
+        balance
+    );
+}
+
+#[test]
+fn migration_without_contract_version() {
+    let admin = Addr::unchecked("admin");
+    let owner = Addr::unchecked("owner");
+
+    let mut app = App::default();
+
+    // Never stores the cw2 contract version
+    fn unversioned_instantiate(
+        _deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        _msg: Empty,
+    ) -> StdResult<Response> {
+        Ok(Response::new())
+    }
+
+    fn unversioned_execute(
+        _deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        _msg: Empty,
+    ) -> StdResult<Response> {
+        Ok(Response::new())
+    }
+
+    fn unversioned_query(_deps: Deps, _env: Env, _msg: Empty) -> StdResult<Binary> {
+        Ok(Binary::default())
+    }
+
+    let old_code_id = app.store_code(Box::new(ContractWrapper::new(
+        unversioned_execute,
+        unversioned_instantiate,
+        unversioned_query,
+    )));
+    let new_code_id = CountingContract::store_code(&mut app);
+
+    let contract = app
+        .instantiate_contract(
+            old_code_id,
+            owner,
+            &Empty {},
+            &[],
+            "Unversioned contract",
+            Some(admin.to_string()),
+        )
+        .unwrap();
+
+    let err: ContractError = app
+        .migrate_contract(admin, contract, &MigrateMsg { parent: None }, new_code_id)
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert!(matches!(err, ContractError::Cw2(_)));
+}
+
+#[test]
+fn migration_with_stale_version() {
+    let admin = Addr::unchecked("admin");
+    let owner = Addr::unchecked("owner");
+
+    let mut app = App::default();
+
+    // Only rolls the cw2 record back, the storage keeps the current layout
+    fn stale_migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> StdResult<Response> {
+        cw2::set_contract_version(deps.storage, "counting-contract", "0.2.0")?;
+
+        Ok(Response::new())
+    }
+
+    let code_id = CountingContract::store_code(&mut app);
+    let stale_code_id = app.store_code(Box::new(
+        ContractWrapper::new(execute, instantiate, query).with_migrate(stale_migrate),
+    ));
+
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        &admin,
+        None,
+        coin(10, ATOM),
+        None,
+    )
+    .unwrap();
+
+    app.migrate_contract(
+        admin.clone(),
+        contract.addr().clone(),
+        &MigrateMsg { parent: None },
+        stale_code_id,
+    )
+    .unwrap();
+
+    let err: ContractError = app
+        .migrate_contract(
+            admin,
+            contract.addr().clone(),
+            &MigrateMsg { parent: None },
+            code_id,
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::AlreadyMigrated {});
+}
+
+#[test]
+fn migration_splits_cw20_counter() {
+    let admin = Addr::unchecked("admin");
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, coins(20, ATOM))
+            .unwrap()
+    });
+
+    // Leaves the contract with the 0.3.0 storage layout, where cw20 donations had no counter of their own
+    fn staged_migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> StdResult<Response> {
+        CW20_COUNTER.remove(deps.storage);
+        cw2::set_contract_version(deps.storage, "counting-contract", "0.3.0")?;
+
+        Ok(Response::new())
+    }
+
+    let code_id = CountingContract::store_code(&mut app);
+    let staged_code_id = app.store_code(Box::new(
+        ContractWrapper::new(execute, instantiate, query).with_migrate(staged_migrate),
+    ));
+
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        &admin,
+        None,
+        coin(10, ATOM),
+        None,
+    )
+    .unwrap();
+
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+
+    app.migrate_contract(
+        admin.clone(),
+        contract.addr().clone(),
+        &MigrateMsg { parent: None },
+        staged_code_id,
+    )
+    .unwrap();
+
+    let contract =
+        CountingContract::migrate(&mut app, contract.into(), code_id, &admin, None).unwrap();
+
+    let state = STATE.query(&app.wrap(), contract.addr().clone()).unwrap();
+    assert_eq!(state.counter, 2);
+
+    let cw20_counter = CW20_COUNTER
+        .query(&app.wrap(), contract.addr().clone())
+        .unwrap();
+    assert_eq!(cw20_counter, 0);
+
+    let resp = contract.query_value(&app).unwrap();
+    assert_eq!(resp, ValueResp { value: 2 });
+}
+
+#[test]
+fn migration_chain() {
+    let admin = Addr::unchecked("admin");
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, coins(10, ATOM))
+            .unwrap()
+    });
+
+    let old_code_id = CountingContract_0_1::store_code(&mut app);
+    let new_code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract_0_1::instantiate(
+        &mut app,
+        old_code_id,
+        &owner,
+        "Counting contract",
+        &admin,
+        None,
+        coin(10, ATOM),
+    )
+    .unwrap();
+
+    let version = cw2::CONTRACT
+        .query(&app.wrap(), contract.addr().clone())
+        .unwrap();
+    assert_eq!(version.version, "0.1.0");
+
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+
+    // 0.1.0 goes through the 0.2.0 schema before reaching the latest one
+    let contract =
+        CountingContract::migrate(&mut app, contract.into(), new_code_id, &admin, None).unwrap();
+
+    let version = cw2::CONTRACT
+        .query(&app.wrap(), contract.addr().clone())
+        .unwrap();
+    assert_eq!(version.version, env!("CARGO_PKG_VERSION"));
+
+    let state = STATE.query(&app.wrap(), contract.addr().clone()).unwrap();
+    assert_eq!(
+        state,
+        State {
+            counter: 1,
+            minimal_donation: coin(10, ATOM),
+            owner: owner.clone(),
+            donating_parent: None
+        }
+    );
+
+    // migrating again to the same version keeps the state untouched
+    let contract =
+        CountingContract::migrate(&mut app, contract.into(), new_code_id, &admin, None).unwrap();
+
+    let state = STATE.query(&app.wrap(), contract.addr().clone()).unwrap();
+    assert_eq!(
+        state,
+        State {
+            counter: 1,
+            minimal_donation: coin(10, ATOM),
+            owner,
+            donating_parent: None
+        }
+    );
+}
+
+#[test]
+fn preview_migrated_state() {
+    let admin = Addr::unchecked("admin");
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+    let parent = Addr::unchecked("parent");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, coins(10, ATOM))
+            .unwrap()
+    });
+
+    // Leaves the contract with the 0.2 storage layout, while already running the current code
+    fn staged_migrate(
+        mut deps: DepsMut,
+        _env: Env,
+        _msg: MigrateMsg,
+    ) -> Result<Response, ContractError> {
+        contract::migrate_0_1_0(deps.branch())?;
+        cw2::set_contract_version(deps.storage, "counting-contract", "0.2.0")?;
+
+        Ok(Response::new())
+    }
+
+    let old_code_id = CountingContract_0_1::store_code(&mut app);
+    let staged_code_id = app.store_code(Box::new(
+        ContractWrapper::new(execute, instantiate, query).with_migrate(staged_migrate),
+    ));
+    let new_code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract_0_1::instantiate(
+        &mut app,
+        old_code_id,
+        &owner,
+        "Counting contract",
+        &admin,
+        None,
+        coin(10, ATOM),
+    )
+    .unwrap();
+
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+
+    let contract =
+        CountingContract::migrate(&mut app, contract.into(), staged_code_id, &admin, None).unwrap();
+
+    let parent = Parent {
+        addr: parent.to_string(),
+        donating_period: 2,
+        part: Decimal::percent(10),
+        fee: None,
+    };
+
+    let preview = contract
+        .query_preview_migrated_state(&app, parent.clone())
+        .unwrap();
+    assert_eq!(
+        preview,
+        MigratedStateResp {
+            counter: 1,
+            minimal_donation: coin(10, ATOM),
+            owner: owner.clone(),
+            donating_parent: Some(2),
+        }
+    );
+
+    // the preview doesn't write anything
+    let version = cw2::CONTRACT
+        .query(&app.wrap(), contract.addr().clone())
+        .unwrap();
+    assert_eq!(version.version, "0.2.0");
+
+    let contract =
+        CountingContract::migrate(&mut app, contract.into(), new_code_id, &admin, parent).unwrap();
+
+    let state = STATE.query(&app.wrap(), contract.addr().clone()).unwrap();
+    assert_eq!(
+        state,
+        State {
+            counter: preview.counter,
+            minimal_donation: preview.minimal_donation,
+            owner: preview.owner,
+            donating_parent: preview.donating_parent,
+        }
+    );
+}
+
+#[test]
+fn migration_with_parent() {
+    let admin = Addr::unchecked("admin");
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+    let parent = Addr::unchecked("parent");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, coins(10, ATOM))
+            .unwrap()
+    });
+
+    let old_code_id = CountingContract_0_1::store_code(&mut app);
+    let new_code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract_0_1::instantiate(
+        &mut app,
+        old_code_id,
+        &owner,
+        "Counting contract",
+        &admin,
+        None,
+        coin(10, ATOM),
+    )
+    .unwrap();
+
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+
+    let contract = CountingContract::migrate(
+        &mut app,
+        contract.into(),
+        new_code_id,
+        &admin,
+        Parent {
+            addr: parent.to_string(),
+            donating_period: 2,
+            part: Decimal::percent(10),
+            fee: None,
+        },
+    )
+    .unwrap();
+
+    let resp = contract.query_value(&app).unwrap();
+    assert_eq!(resp, ValueResp { value: 1 });
+
+    let state = STATE.query(&app.wrap(), contract.addr().clone()).unwrap();
+    assert_eq!(
+        state,
+        State {
+            counter: 1,
+            minimal_donation: coin(10, ATOM),
+            owner,
+            donating_parent: Some(2)
+        }
+    );
+
+    let parent_donation = PARENT_DONATION
+        .query(&app.wrap(), contract.addr().clone())
+        .unwrap();
+    assert_eq!(
+        parent_donation,
+        ParentDonation {
+            address: parent,
+            donating_parent_period: 2,
+            part: Decimal::percent(10),
+            fee: None,
+        }
+    )
+}
+
+#[test]
+fn migration_same_version() {
+    let admin = Addr::unchecked("admin");
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, coins(10, ATOM))
+            .unwrap()
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract_0_1::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        &admin,
+        None,
+        coin(10, ATOM),
+    )
+    .unwrap();
+
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+
+    let contract =
+        CountingContract::migrate(&mut app, contract.into(), code_id, &admin, None).unwrap();
+
+    let resp = contract.query_value(&app).unwrap();
+    assert_eq!(resp, ValueResp { value: 1 });
+
+    let state = STATE.query(&app.wrap(), contract.addr().clone()).unwrap();
+    assert_eq!(
+        state,
+        State {
+            counter: 1,
+            minimal_donation: coin(10, ATOM),
+            owner,
+            donating_parent: None
+        }
+    );
+}
+
+#[test]
+fn donation_parent() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, coins(20, "atom"))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let parent_contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(0, ATOM),
+        None,
+    )
+    .unwrap();
+
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        Parent {
+            addr: parent_contract.addr().to_string(),
+            donating_period: 2,
+            part: Decimal::percent(10),
+            fee: None,
+        },
+    )
+    .unwrap();
+
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+
+    let resp = parent_contract.query_value(&app).unwrap();
+    assert_eq!(resp, ValueResp { value: 1 });
+
+    let resp = contract.query_value(&app).unwrap();
+    assert_eq!(resp, ValueResp { value: 2 });
+
+    assert_eq!(app.wrap().query_all_balances(owner).unwrap(), vec![]);
+    assert_eq!(app.wrap().query_all_balances(sender).unwrap(), vec![]);
+    assert_eq!(
+        app.wrap().query_all_balances(contract.addr()).unwrap(),
+        coins(18, ATOM)
+    );
+    assert_eq!(
+        app.wrap()
+            .query_all_balances(parent_contract.addr())
+            .unwrap(),
+        coins(2, ATOM)
+    );
+}
+
+#[test]
+fn donation_extra_parents() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, coins(20, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let first_parent = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(0, ATOM),
+        None,
+    )
+    .unwrap();
+
+    let second_parent = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(0, ATOM),
+        None,
+    )
+    .unwrap();
+
+    let contract = CountingContract::instantiate_with_msg(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        &InstantiateMsg {
+            minimal_donation: coin(10, ATOM),
+            extra_parents: vec![
+                Parent {
+                    addr: first_parent.addr().to_string(),
+                    donating_period: 1,
+                    part: Decimal::percent(10),
+                    fee: None,
+                },
+                Parent {
+                    addr: second_parent.addr().to_string(),
+                    donating_period: 2,
+                    part: Decimal::percent(20),
+                    fee: None,
+                },
+            ],
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    assert!(contract.query_has_parent(&app).unwrap());
+    assert!(contract.query_would_forward_to_parent(&app).unwrap());
+    assert_eq!(
+        contract.query_role(&app).unwrap(),
+        RoleResp::Child {
+            parent: first_parent.addr().clone()
+        }
+    );
+
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+
+    assert_eq!(
+        app.wrap().query_all_balances(first_parent.addr()).unwrap(),
+        coins(1, ATOM)
+    );
+    assert_eq!(
+        app.wrap().query_all_balances(second_parent.addr()).unwrap(),
+        vec![]
+    );
+
+    // Both parents are due on the next donation, 10% and 20% of the 9 atom balance
+    assert_eq!(
+        contract.query_next_forward(&app).unwrap(),
+        Some(NextForwardResp {
+            periods_remaining: 0,
+            estimated_amount: coins(1, ATOM),
+        })
+    );
+
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+
+    let resp = first_parent.query_value(&app).unwrap();
+    assert_eq!(resp, ValueResp { value: 2 });
+
+    let resp = second_parent.query_value(&app).unwrap();
+    assert_eq!(resp, ValueResp { value: 1 });
+
+    assert_eq!(
+        app.wrap().query_all_balances(contract.addr()).unwrap(),
+        coins(15, ATOM)
+    );
+    assert_eq!(
+        app.wrap().query_all_balances(first_parent.addr()).unwrap(),
+        coins(2, ATOM)
+    );
+    assert_eq!(
+        app.wrap().query_all_balances(second_parent.addr()).unwrap(),
+        coins(3, ATOM)
+    );
+
+    contract.remove_parent(&mut app, &owner).unwrap();
+
+    assert!(!contract.query_has_parent(&app).unwrap());
+    assert!(contract
+        .query_describe(&app)
+        .unwrap()
+        .contains("parent: none"));
+    assert_eq!(contract.query_next_forward(&app).unwrap(), None);
+}
+
+#[test]
+fn instantiate_zero_parent_period() {
+    let owner = Addr::unchecked("owner");
+
+    let mut app = App::default();
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let err = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        Parent {
+            addr: "parent".to_owned(),
+            donating_period: 0,
+            part: Decimal::percent(10),
+            fee: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidPeriod {});
+}
+
+#[test]
+fn instantiate_parent_parts_over_whole() {
+    let owner = Addr::unchecked("owner");
+
+    let mut app = App::default();
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let err = CountingContract::instantiate_with_msg(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        &InstantiateMsg {
+            minimal_donation: coin(10, ATOM),
+            parent: Some(Parent {
+                addr: "parent".to_owned(),
+                donating_period: 1,
+                part: Decimal::percent(60),
+                fee: None,
+            }),
+            extra_parents: vec![Parent {
+                addr: "other_parent".to_owned(),
+                donating_period: 1,
+                part: Decimal::percent(50),
+                fee: None,
+            }],
+            ..Default::default()
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidParentParts {});
+}
+
+#[test]
+fn donation_parent_forward_depth() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, coins(20, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let root_contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(0, ATOM),
+        None,
+    )
+    .unwrap();
+
+    let parent_contract = CountingContract::instantiate_with_msg(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        &InstantiateMsg {
+            minimal_donation: coin(0, ATOM),
+            parent: Some(Parent {
+                addr: root_contract.addr().to_string(),
+                donating_period: 1,
+                part: Decimal::percent(50),
+                fee: None,
+            }),
+            max_forward_depth: Some(1),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        Parent {
+            addr: parent_contract.addr().to_string(),
+            donating_period: 1,
+            part: Decimal::percent(50),
+            fee: None,
+        },
+    )
+    .unwrap();
+
+    contract
+        .donate(&mut app, &sender, &coins(20, ATOM))
+        .unwrap();
+
+    let resp = parent_contract.query_value(&app).unwrap();
+    assert_eq!(resp, ValueResp { value: 1 });
+
+    let resp = root_contract.query_value(&app).unwrap();
+    assert_eq!(resp, ValueResp { value: 0 });
+
+    assert_eq!(
+        app.wrap().query_all_balances(contract.addr()).unwrap(),
+        coins(10, ATOM)
+    );
+    assert_eq!(
+        app.wrap()
+            .query_all_balances(parent_contract.addr())
+            .unwrap(),
+        coins(10, ATOM)
+    );
+    assert_eq!(
+        app.wrap().query_all_balances(root_contract.addr()).unwrap(),
+        vec![]
+    );
+}
+
+#[test]
+fn register_child() {
+    let owner = Addr::unchecked("owner");
+
+    let mut app = App::default();
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let parent_contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        None,
+    )
+    .unwrap();
+
+    assert_eq!(
+        parent_contract.query_children(&app, None, None).unwrap(),
+        Vec::<Addr>::new()
+    );
+
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        Parent {
+            addr: parent_contract.addr().to_string(),
+            donating_period: 1,
+            part: Decimal::percent(50),
+            fee: None,
+        },
+    )
+    .unwrap();
+
+    assert_eq!(
+        parent_contract.query_children(&app, None, None).unwrap(),
+        vec![contract.addr().clone()]
+    );
+    assert_eq!(
+        parent_contract
+            .query_children(&app, contract.addr().to_string(), None)
+            .unwrap(),
+        Vec::<Addr>::new()
+    );
+
+    // only contracts naming this one as their parent can register
+    let err = app
+        .execute_contract(
+            owner.clone(),
+            parent_contract.addr().clone(),
+            &ExecMsg::RegisterChild {},
+            &[],
+        )
+        .unwrap_err()
+        .downcast::<ContractError>()
+        .unwrap();
+    assert_eq!(
+        err,
+        ContractError::NotChild {
+            addr: owner.to_string()
+        }
+    );
+}
+
+#[test]
+fn donation_chain_propagates() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, coins(40, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let chain = CountingContract::instantiate_chain(
+        &mut app,
+        code_id,
+        &owner,
+        3,
+        coin(0, ATOM),
+        1,
+        Decimal::percent(50),
+    )
+    .unwrap();
+    let [root, middle, leaf] = &chain[..] else {
+        panic!("expected a 3-deep chain");
+    };
+
+    leaf.donate(&mut app, &sender, &coins(40, ATOM)).unwrap();
+
+    for contract in &chain {
+        let resp = contract.query_value(&app).unwrap();
+        assert_eq!(resp, ValueResp { value: 1 });
+    }
+
+    assert_eq!(
+        app.wrap().query_all_balances(leaf.addr()).unwrap(),
+        coins(20, ATOM)
+    );
+    assert_eq!(
+        app.wrap().query_all_balances(middle.addr()).unwrap(),
+        coins(10, ATOM)
+    );
+    assert_eq!(
+        app.wrap().query_all_balances(root.addr()).unwrap(),
+        coins(10, ATOM)
+    );
+}
+
+#[test]
+fn parent_period() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, coins(20, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let parent_contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(0, ATOM),
+        None,
+    )
+    .unwrap();
+
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        Parent {
+            addr: parent_contract.addr().to_string(),
+            donating_period: 3,
+            part: Decimal::percent(10),
+            fee: None,
+        },
+    )
+    .unwrap();
+
+    assert_eq!(parent_contract.query_parent_period(&app).unwrap(), None);
+    assert_eq!(contract.query_parent_period(&app).unwrap(), Some(3));
+
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+
+    let state = STATE.query(&app.wrap(), contract.addr().clone()).unwrap();
+    assert_eq!(state.donating_parent, Some(1));
+    assert_eq!(contract.query_parent_period(&app).unwrap(), Some(3));
+}
+
+#[test]
+fn reset_parent_countdown() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, coins(20, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let parent_contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(0, ATOM),
+        None,
+    )
+    .unwrap();
+
+    let err = parent_contract
+        .reset_parent_countdown(&mut app, &owner)
+        .unwrap_err();
+    assert_eq!(err, ContractError::NoParent {});
+
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        Parent {
+            addr: parent_contract.addr().to_string(),
+            donating_period: 3,
+            part: Decimal::percent(10),
+            fee: None,
+        },
+    )
+    .unwrap();
+
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+
+    let state = STATE.query(&app.wrap(), contract.addr().clone()).unwrap();
+    assert_eq!(state.donating_parent, Some(1));
+
+    let err = contract
+        .reset_parent_countdown(&mut app, &sender)
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Unauthorized {
+            owner: owner.to_string()
+        }
+    );
+
+    contract.reset_parent_countdown(&mut app, &owner).unwrap();
+
+    let state = STATE.query(&app.wrap(), contract.addr().clone()).unwrap();
+    assert_eq!(state.donating_parent, Some(3));
+}
+
+#[test]
+fn set_parent_period() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, coins(30, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let parent_contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(0, ATOM),
+        None,
+    )
+    .unwrap();
+
+    let err = parent_contract
+        .set_parent_period(&mut app, &owner, 3)
+        .unwrap_err();
+    assert_eq!(err, ContractError::NoParent {});
+
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        Parent {
+            addr: parent_contract.addr().to_string(),
+            donating_period: 2,
+            part: Decimal::percent(10),
+            fee: None,
+        },
+    )
+    .unwrap();
+
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+
+    let err = contract
+        .set_parent_period(&mut app, &sender, 3)
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Unauthorized {
+            owner: owner.to_string()
+        }
+    );
+
+    let err = contract.set_parent_period(&mut app, &owner, 0).unwrap_err();
+    assert_eq!(err, ContractError::InvalidPeriod {});
+
+    contract.set_parent_period(&mut app, &owner, 3).unwrap();
+
+    let state = STATE.query(&app.wrap(), contract.addr().clone()).unwrap();
+    assert_eq!(state.donating_parent, Some(3));
+
+    let parent_donation = PARENT_DONATION
+        .query(&app.wrap(), contract.addr().clone())
+        .unwrap();
+    assert_eq!(parent_donation.donating_parent_period, 3);
+
+    // the countdown restarted, so two more donations don't reach the parent yet
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+
+    let resp = parent_contract.query_value(&app).unwrap();
+    assert_eq!(resp, ValueResp { value: 0 });
+
+    let state = STATE.query(&app.wrap(), contract.addr().clone()).unwrap();
+    assert_eq!(state.donating_parent, Some(1));
+}
+
+#[test]
+fn role() {
+    let owner = Addr::unchecked("owner");
+
+    let mut app = App::default();
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let parent_contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(0, ATOM),
+        None,
+    )
+    .unwrap();
+
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        Parent {
+            addr: parent_contract.addr().to_string(),
+            donating_period: 2,
+            part: Decimal::percent(10),
+            fee: None,
+        },
+    )
+    .unwrap();
+
+    assert_eq!(parent_contract.query_role(&app).unwrap(), RoleResp::Root);
+    assert_eq!(
+        contract.query_role(&app).unwrap(),
+        RoleResp::Child {
+            parent: parent_contract.addr().clone()
+        }
+    );
+}
+
+#[test]
+fn remove_parent() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, coins(30, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let parent_contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(0, ATOM),
+        None,
+    )
+    .unwrap();
+
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        Parent {
+            addr: parent_contract.addr().to_string(),
+            donating_period: 2,
+            part: Decimal::percent(10),
+            fee: None,
+        },
+    )
+    .unwrap();
+
+    assert!(contract.query_has_parent(&app).unwrap());
+    assert!(!parent_contract.query_has_parent(&app).unwrap());
+
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+
+    let err = contract.remove_parent(&mut app, &sender).unwrap_err();
     assert_eq!(
-        state,
-        State {
-            counter: 1,
-            minimal_donation: coin(10, ATOM),
-            owner,
-            donating_parent: Some(2)
+        err,
+        ContractError::Unauthorized {
+            owner: owner.to_string()
         }
     );
 
-    let parent_donation = PARENT_DONATION
-        .query(&app.wrap(), contract.addr().clone())
+    contract.remove_parent(&mut app, &owner).unwrap();
+    assert!(!contract.query_has_parent(&app).unwrap());
+
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
         .unwrap();
+
+    let resp = contract.query_value(&app).unwrap();
+    assert_eq!(resp, ValueResp { value: 3 });
+
+    let resp = parent_contract.query_value(&app).unwrap();
+    assert_eq!(resp, ValueResp { value: 0 });
+
     assert_eq!(
-        parent_donation,
-        ParentDonation {
-            address: parent,
-            donating_parent_period: 2,
+        app.wrap().query_all_balances(contract.addr()).unwrap(),
+        coins(30, ATOM)
+    );
+    assert_eq!(
+        app.wrap()
+            .query_all_balances(parent_contract.addr())
+            .unwrap(),
+        vec![]
+    );
+}
+
+#[test]
+fn schema() {
+    let api = api();
+
+    assert_eq!(api.contract_name, env!("CARGO_PKG_NAME"));
+    assert_eq!(api.contract_version, env!("CARGO_PKG_VERSION"));
+    assert!(api.migrate.is_some());
+
+    api.render().to_string().unwrap();
+}
+
+#[test]
+fn donation_parent_reentrancy() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, coins(10, ATOM))
+            .unwrap();
+    });
+
+    // Donates back to whoever donated to it
+    fn malicious_execute(
+        _deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        _msg: ExecMsg,
+    ) -> StdResult<Response> {
+        let msg = WasmMsg::Execute {
+            contract_addr: info.sender.to_string(),
+            msg: to_binary(&ExecMsg::Donate {
+                forward_depth: None,
+                idempotency_key: None,
+            })?,
+            funds: vec![],
+        };
+
+        Ok(Response::new().add_message(msg))
+    }
+
+    fn malicious_instantiate(
+        _deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        _msg: Empty,
+    ) -> StdResult<Response> {
+        Ok(Response::new())
+    }
+
+    fn malicious_query(_deps: Deps, _env: Env, _msg: Empty) -> StdResult<Binary> {
+        Ok(Binary::default())
+    }
+
+    let malicious_code_id = app.store_code(Box::new(ContractWrapper::new(
+        malicious_execute,
+        malicious_instantiate,
+        malicious_query,
+    )));
+    let code_id = CountingContract::store_code(&mut app);
+
+    let parent = app
+        .instantiate_contract(
+            malicious_code_id,
+            owner.clone(),
+            &Empty {},
+            &[],
+            "Malicious parent",
+            None,
+        )
+        .unwrap();
+
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        Parent {
+            addr: parent.to_string(),
+            donating_period: 1,
             part: Decimal::percent(10),
+            fee: None,
+        },
+    )
+    .unwrap();
+
+    let err = contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap_err();
+    assert_eq!(err, ContractError::Reentrancy {});
+
+    let resp = contract.query_value(&app).unwrap();
+    assert_eq!(resp, ValueResp { value: 0 });
+
+    assert_eq!(
+        app.wrap().query_all_balances(sender).unwrap(),
+        coins(10, ATOM)
+    );
+}
+
+#[test]
+fn sweep_cw20() {
+    let owner = Addr::unchecked("owner");
+    let holder = Addr::unchecked("holder");
+    let recipient = Addr::unchecked("recipient");
+
+    let mut app = App::default();
+
+    const BALANCES: Map<&Addr, Uint128> = Map::new("balances");
+
+    // A bare cw20 token, the instantiating address gets the whole supply
+    fn token_execute(
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        msg: Cw20ExecuteMsg,
+    ) -> StdResult<Response> {
+        let Cw20ExecuteMsg::Transfer { recipient, amount } = msg;
+        let recipient = deps.api.addr_validate(&recipient)?;
+
+        BALANCES.update(deps.storage, &info.sender, |balance| -> StdResult<_> {
+            Ok(balance.unwrap_or_default().checked_sub(amount)?)
+        })?;
+        BALANCES.update(deps.storage, &recipient, |balance| -> StdResult<_> {
+            Ok(balance.unwrap_or_default() + amount)
+        })?;
+
+        Ok(Response::new())
+    }
+
+    fn token_instantiate(
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        supply: Uint128,
+    ) -> StdResult<Response> {
+        BALANCES.save(deps.storage, &info.sender, &supply)?;
+
+        Ok(Response::new())
+    }
+
+    fn token_query(deps: Deps, _env: Env, msg: Cw20QueryMsg) -> StdResult<Binary> {
+        let Cw20QueryMsg::Balance { address } = msg;
+        let address = deps.api.addr_validate(&address)?;
+        let balance = BALANCES
+            .may_load(deps.storage, &address)?
+            .unwrap_or_default();
+
+        to_binary(&Cw20BalanceResponse { balance })
+    }
+
+    let token_code_id = app.store_code(Box::new(ContractWrapper::new(
+        token_execute,
+        token_instantiate,
+        token_query,
+    )));
+    let code_id = CountingContract::store_code(&mut app);
+
+    let token = app
+        .instantiate_contract(
+            token_code_id,
+            holder.clone(),
+            &Uint128::new(100),
+            &[],
+            "Token",
+            None,
+        )
+        .unwrap();
+
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        None,
+    )
+    .unwrap();
+
+    // sent with a plain transfer, so the contract never gets notified
+    app.execute_contract(
+        holder.clone(),
+        token.clone(),
+        &Cw20ExecuteMsg::Transfer {
+            recipient: contract.addr().to_string(),
+            amount: Uint128::new(40),
+        },
+        &[],
+    )
+    .unwrap();
+
+    let err = contract
+        .sweep_cw20(&mut app, &holder, &token, &recipient)
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Unauthorized {
+            owner: owner.to_string()
         }
+    );
+
+    contract
+        .sweep_cw20(&mut app, &owner, &token, &recipient)
+        .unwrap();
+
+    let balance = |addr: &Addr| -> Uint128 {
+        app.wrap()
+            .query_wasm_smart::<Cw20BalanceResponse>(
+                token.clone(),
+                &Cw20QueryMsg::Balance {
+                    address: addr.to_string(),
+                },
+            )
+            .unwrap()
+            .balance
+    };
+    assert_eq!(balance(contract.addr()), Uint128::zero());
+    assert_eq!(balance(&recipient), Uint128::new(40));
+    assert_eq!(balance(&holder), Uint128::new(60));
+}
+
+#[test]
+fn would_forward_to_parent() {
+    let owner = Addr::unchecked("owner");
+    let parent = Addr::unchecked("parent");
+
+    let mut app = App::default();
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        Parent {
+            addr: parent.to_string(),
+            donating_period: 1,
+            part: Decimal::percent(10),
+            fee: None,
+        },
+    )
+    .unwrap();
+
+    assert!(contract.query_would_forward_to_parent(&app).unwrap());
+
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        Parent {
+            addr: parent.to_string(),
+            donating_period: 3,
+            part: Decimal::percent(10),
+            fee: None,
+        },
+    )
+    .unwrap();
+
+    assert!(!contract.query_would_forward_to_parent(&app).unwrap());
+
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        None,
     )
+    .unwrap();
+
+    assert!(!contract.query_would_forward_to_parent(&app).unwrap());
 }
 
 #[test]
-fn migration_same_version() {
-    let admin = Addr::unchecked("admin");
+fn next_forward() {
     let owner = Addr::unchecked("owner");
     let sender = Addr::unchecked("sender");
+    let parent = Addr::unchecked("parent");
 
     let mut app = App::new(|router, _api, storage| {
         router
             .bank
-            .init_balance(storage, &sender, coins(10, ATOM))
-            .unwrap()
+            .init_balance(storage, &sender, coins(20, ATOM))
+            .unwrap();
     });
 
     let code_id = CountingContract::store_code(&mut app);
 
-    let contract = CountingContract_0_1::instantiate(
+    let contract = CountingContract::instantiate(
         &mut app,
         code_id,
         &owner,
         "Counting contract",
-        &admin,
+        None,
         None,
         coin(10, ATOM),
+        Parent {
+            addr: parent.to_string(),
+            donating_period: 3,
+            part: Decimal::percent(30),
+            fee: None,
+        },
     )
     .unwrap();
 
+    assert_eq!(
+        contract.query_next_forward(&app).unwrap(),
+        Some(NextForwardResp {
+            periods_remaining: 2,
+            estimated_amount: vec![],
+        })
+    );
+
     contract
         .donate(&mut app, &sender, &coins(10, ATOM))
         .unwrap();
 
-    let contract =
-        CountingContract::migrate(&mut app, contract.into(), code_id, &admin, None).unwrap();
+    assert_eq!(
+        contract.query_next_forward(&app).unwrap(),
+        Some(NextForwardResp {
+            periods_remaining: 1,
+            estimated_amount: coins(3, ATOM),
+        })
+    );
 
-    let resp = contract.query_value(&app).unwrap();
-    assert_eq!(resp, ValueResp { value: 1 });
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
 
-    let state = STATE.query(&app.wrap(), contract.addr().clone()).unwrap();
+    // 30% of the 20 atom balance
     assert_eq!(
-        state,
-        State {
-            counter: 1,
-            minimal_donation: coin(10, ATOM),
-            owner,
-            donating_parent: None
-        }
+        contract.query_next_forward(&app).unwrap(),
+        Some(NextForwardResp {
+            periods_remaining: 0,
+            estimated_amount: coins(6, ATOM),
+        })
     );
+    assert!(contract.query_would_forward_to_parent(&app).unwrap());
+
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        None,
+    )
+    .unwrap();
+
+    assert_eq!(contract.query_next_forward(&app).unwrap(), None);
 }
 
 #[test]
-fn donation_parent() {
+fn donation_parent_fee() {
     let owner = Addr::unchecked("owner");
     let sender = Addr::unchecked("sender");
 
     let mut app = App::new(|router, _api, storage| {
         router
             .bank
-            .init_balance(storage, &sender, coins(20, "atom"))
+            .init_balance(storage, &sender, coins(100, ATOM))
             .unwrap();
     });
 
@@ -565,35 +7121,29 @@ fn donation_parent() {
         coin(10, ATOM),
         Parent {
             addr: parent_contract.addr().to_string(),
-            donating_period: 2,
+            donating_period: 1,
             part: Decimal::percent(10),
+            fee: Some(coin(3, ATOM)),
         },
     )
     .unwrap();
 
     contract
-        .donate(&mut app, &sender, &coins(10, ATOM))
-        .unwrap();
-    contract
-        .donate(&mut app, &sender, &coins(10, ATOM))
+        .donate(&mut app, &sender, &coins(100, ATOM))
         .unwrap();
 
     let resp = parent_contract.query_value(&app).unwrap();
     assert_eq!(resp, ValueResp { value: 1 });
 
-    let resp = contract.query_value(&app).unwrap();
-    assert_eq!(resp, ValueResp { value: 2 });
-
-    assert_eq!(app.wrap().query_all_balances(owner).unwrap(), vec![]);
-    assert_eq!(app.wrap().query_all_balances(sender).unwrap(), vec![]);
-    assert_eq!(
-        app.wrap().query_all_balances(contract.addr()).unwrap(),
-        coins(18, ATOM)
-    );
+    // 10% of 100 is forwarded, minus the fee of 3
     assert_eq!(
         app.wrap()
             .query_all_balances(parent_contract.addr())
             .unwrap(),
-        coins(2, ATOM)
+        coins(7, ATOM)
+    );
+    assert_eq!(
+        app.wrap().query_all_balances(contract.addr()).unwrap(),
+        coins(93, ATOM)
     );
 }
@@ -1,7 +1,9 @@
-use cosmwasm_std::{Addr, Coin, Decimal};
-use cw_storage_plus::Item;
+use cosmwasm_std::{Addr, Coin, Decimal, Timestamp, Uint128};
+use cw_storage_plus::{Item, Map};
 use serde::{Deserialize, Serialize};
 
+use crate::msg::{DonationRecord, InstantiateMsg};
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct State {
     pub counter: u64,
@@ -15,7 +17,91 @@ pub struct ParentDonation {
     pub address: Addr,
     pub donating_parent_period: u64,
     pub part: Decimal,
+    pub fee: Option<Coin>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct StreakInfo {
+    pub last_height: u64,
+    pub streak: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ScheduledDonation {
+    pub every_blocks: u64,
+    pub amount: Coin,
+    pub deposit: Uint128,
+    pub next_height: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+pub struct RefundPool {
+    pub generation: u64,
+    pub shares: Uint128,
+    pub amount: Uint128,
 }
 
 pub const STATE: Item<State> = Item::new("state");
 pub const PARENT_DONATION: Item<ParentDonation> = Item::new("parent_donation");
+// Every extra parent with its own countdown to the next forwarding
+pub const EXTRA_PARENTS: Map<&Addr, (ParentDonation, u64)> = Map::new("extra_parents");
+pub const ALLOWED_DONORS: Item<Vec<Addr>> = Item::new("allowed_donors");
+pub const BLOCKED_DONORS: Map<&Addr, ()> = Map::new("blocked_donors");
+pub const MAX_DONATION: Map<&str, Uint128> = Map::new("max_donation");
+pub const EVENT_PREFIX: Item<String> = Item::new("event_prefix");
+pub const COUNTER_AT_HEIGHT: Map<u64, u64> = Map::new("counter_at_height");
+pub const TOTAL_DONATED: Map<&str, Uint128> = Map::new("total_donated");
+pub const TOTAL_WITHDRAWN: Map<&str, Uint128> = Map::new("total_withdrawn");
+pub const MAX_COUNTER: Item<u64> = Item::new("max_counter");
+pub const LOCK: Item<bool> = Item::new("lock");
+pub const DONOR_TOTALS: Map<&Addr, Uint128> = Map::new("donor_totals");
+pub const GRANDFATHERED_UNTIL: Map<&str, u64> = Map::new("grandfathered_until");
+pub const DONOR_COUNTS: Map<&Addr, u64> = Map::new("donor_counts");
+pub const REFUND_EXCESS: Item<bool> = Item::new("refund_excess");
+pub const ROUND_SIZE: Item<u64> = Item::new("round_size");
+pub const ROUNDS_COMPLETED: Item<u64> = Item::new("rounds_completed");
+// Each donor's native donations counted in a round, keyed by `ROUNDS_COMPLETED` at the time
+pub const ROUND_DONOR_COUNTS: Map<&Addr, (u64, u64)> = Map::new("round_donor_counts");
+pub const WITHDRAW_CUT: Item<(Addr, Decimal)> = Item::new("withdraw_cut");
+pub const ACCEPT_FUNDS_WHEN_FREE: Item<bool> = Item::new("accept_funds_when_free");
+pub const PAUSED: Item<bool> = Item::new("paused");
+pub const LAST_DONATION: Map<&Addr, Vec<Coin>> = Map::new("last_donation");
+pub const VANITY_COUNTER: Item<u64> = Item::new("vanity_counter");
+pub const WITHDRAW_COOLDOWN: Item<u64> = Item::new("withdraw_cooldown");
+pub const LAST_WITHDRAW: Item<Timestamp> = Item::new("last_withdraw");
+pub const RESERVE: Item<Coin> = Item::new("reserve");
+pub const STIPEND: Item<(Coin, u64)> = Item::new("stipend");
+pub const LAST_STIPEND_CLAIM: Item<u64> = Item::new("last_stipend_claim");
+pub const DECAY_PER_BLOCK: Item<u64> = Item::new("decay_per_block");
+pub const LAST_TOUCH_HEIGHT: Item<u64> = Item::new("last_touch_height");
+pub const LAST_DONOR: Item<Addr> = Item::new("last_donor");
+pub const MAX_FORWARD_DEPTH: Item<u8> = Item::new("max_forward_depth");
+pub const STREAK_WINDOW: Item<u64> = Item::new("streak_window");
+pub const STREAKS: Map<&Addr, StreakInfo> = Map::new("streaks");
+// cw20 donations are counted apart, `State::counter` only holds the native ones
+pub const CW20_COUNTER: Item<u64> = Item::new("cw20_counter");
+// How many of each donor's `DONOR_COUNTS` were cw20 donations
+pub const CW20_DONOR_COUNTS: Map<&Addr, u64> = Map::new("cw20_donor_counts");
+pub const CW20_MINIMAL_DONATION: Item<(Addr, Uint128)> = Item::new("cw20_minimal_donation");
+// Ring buffer of the latest donations, slot `n % RECENT_DONATIONS_SIZE` holds the n-th one
+pub const RECENT_DONATIONS_SIZE: u64 = 50;
+pub const RECENT_DONATIONS: Map<u64, DonationRecord> = Map::new("recent_donations");
+pub const DONATIONS_RECORDED: Item<u64> = Item::new("donations_recorded");
+pub const LAST_MODIFIED_HEIGHT: Item<u64> = Item::new("last_modified_height");
+pub const SCHEDULED_DONATIONS: Map<&Addr, ScheduledDonation> = Map::new("scheduled_donations");
+// Deposits of every scheduled donation per denom, they stay the donors' until applied or cancelled
+pub const SCHEDULED_ESCROW: Map<&str, Uint128> = Map::new("scheduled_escrow");
+pub const CHILDREN: Map<&Addr, ()> = Map::new("children");
+pub const IDEMPOTENCY_KEYS: Map<&str, ()> = Map::new("idempotency_keys");
+pub const INIT_PARAMS: Item<InstantiateMsg> = Item::new("init_params");
+pub const REFUNDABLE: Item<bool> = Item::new("refundable");
+pub const TOLERANCE: Item<Uint128> = Item::new("tolerance");
+pub const MILESTONE_SIZE: Item<u64> = Item::new("milestone_size");
+pub const WITHDRAW_DESTINATION: Item<Addr> = Item::new("withdraw_destination");
+pub const RECEIPTS_ISSUED: Item<u64> = Item::new("receipts_issued");
+pub const PREDECESSOR: Item<Addr> = Item::new("predecessor");
+pub const MINIMAL_DONATION_BPS: Item<bool> = Item::new("minimal_donation_bps");
+// What is still refundable per denom, split between the donors by their shares. Once a pool runs
+// dry its generation moves on, and shares from an older generation are worth nothing.
+pub const REFUND_POOLS: Map<&str, RefundPool> = Map::new("refund_pools");
+pub const REFUND_SHARES: Map<(&Addr, &str), (u64, Uint128)> = Map::new("refund_shares");
@@ -1,10 +1,18 @@
-use cosmwasm_schema::write_api;
-use counting_contract::msg::{ExecMsg, InstantiateMsg, QueryMsg};
+use std::{
+    env::current_dir,
+    fs::{create_dir_all, write},
+};
 
 fn main() {
-    write_api! {
-        instantiate: InstantiateMsg,
-        execute: ExecMsg,
-        query: QueryMsg
+    let out_dir = current_dir().unwrap().join("schema");
+    let raw_dir = out_dir.join("raw");
+    create_dir_all(&raw_dir).unwrap();
+
+    let api = counting_contract::msg::api().render();
+    let path = out_dir.join(format!("{}.json", api.contract_name));
+    write(path, api.to_string().unwrap() + "\n").unwrap();
+
+    for (filename, json) in api.to_schema_files().unwrap() {
+        write(raw_dir.join(filename), json + "\n").unwrap();
     }
 }
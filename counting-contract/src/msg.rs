@@ -1,4 +1,4 @@
-use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_schema::{cw_serde, generate_api, Api, QueryResponses};
 use cosmwasm_std::Coin;
 
 #[cw_serde]
@@ -45,3 +45,12 @@ pub struct ValueResp {
     // Define a field called value of type u64.
     pub value: u64,
 }
+
+// The messages `bin/schema.rs` writes out, kept here so the tests check the very same list
+pub fn api() -> Api {
+    generate_api! {
+        instantiate: InstantiateMsg,
+        execute: ExecMsg,
+        query: QueryMsg,
+    }
+}
@@ -1,7 +1,7 @@
 use cosmwasm_std::{coin, coins, Addr};
 use cw_multi_test::App;
 
-use crate::error::ContractError;
+use crate::{error::ContractError, msg::api};
 
 use super::contract::CountingContract;
 
@@ -341,3 +341,13 @@ fn unauthorized_reset() {
         }
     );
 }
+
+#[test]
+fn schema() {
+    let api = api();
+
+    assert_eq!(api.contract_name, env!("CARGO_PKG_NAME"));
+    assert_eq!(api.contract_version, env!("CARGO_PKG_VERSION"));
+
+    api.render().to_string().unwrap();
+}